@@ -1,27 +1,30 @@
 use {
-    crate::{cli::SlotRange, process::process_event_files},
+    crate::{
+        cli::SlotRange,
+        decode::{decode_and_resolve, DecodeFailureCounts, REPORTED_IP_COUNT},
+        fee::transaction_fee,
+    },
     agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{process::process_event_files_from, seek_index::SeekTarget},
     solana_address::Address,
     solana_alt_store::Store,
-    solana_borsh::v1::try_from_slice_unchecked,
     solana_clock::Slot,
-    solana_compute_budget_interface::ComputeBudgetInstruction,
     solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
-    solana_sdk_ids::compute_budget,
-    solana_transaction::{
-        sanitized::SanitizedTransaction,
-        versioned::{sanitized::SanitizedVersionedTransaction, VersionedTransaction},
-    },
     std::{
-        collections::{HashMap, HashSet},
-        ops::RangeInclusive,
+        collections::HashMap,
+        ops::{ControlFlow, RangeInclusive},
         path::PathBuf,
     },
 };
 
 pub fn account_usage(event_file_paths: &[PathBuf], slot_range: SlotRange) -> std::io::Result<()> {
+    let start_slot = slot_range.start_slot;
     let mut handler = AccountUsageHandler::new(slot_range);
-    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    process_event_files_from(
+        event_file_paths,
+        SeekTarget::Slot(start_slot),
+        &mut |event| handler.handle_event(event),
+    )?;
     handler.report();
     Ok(())
 }
@@ -29,7 +32,6 @@ pub fn account_usage(event_file_paths: &[PathBuf], slot_range: SlotRange) -> std
 struct AccountUsageHandler {
     range: RangeInclusive<Slot>,
     current_packet_batches: Vec<BankingPacketBatch>,
-    done: bool,
     alt_store: Store,
 }
 
@@ -40,19 +42,18 @@ impl AccountUsageHandler {
         Self {
             range: slot_range.start_slot..=slot_range.end_slot,
             current_packet_batches: Vec::new(),
-            done: false,
             alt_store: Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store"),
         }
     }
 
-    pub fn handle_event(&mut self, TimedTracedEvent(_timestamp, event): TimedTracedEvent) {
-        if self.done {
-            return;
-        }
-
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
         match event {
             TracedEvent::PacketBatch(label, packet_batches) => {
-                self.handle_packet_batches(label, packet_batches)
+                self.handle_packet_batches(label, packet_batches);
+                ControlFlow::Continue(())
             }
             TracedEvent::BlockAndBankHash(slot, _, _) => self.handle_block_and_bank_hash(slot),
         }
@@ -63,40 +64,46 @@ impl AccountUsageHandler {
     /// - Per account:
     ///     - Number of reads and writes
     ///     - write priority min, max, avg
+    ///     - fee min, max, avg
     pub fn report(&self) {
         // Build account usage statistics
         let mut account_usage_statistics = HashMap::new();
 
-        for tx in self
+        let mut decode_failures = DecodeFailureCounts::default();
+
+        for packet in self
             .current_packet_batches
             .iter()
             .flat_map(|b| b.iter().flat_map(|b| b.iter()))
-            .filter_map(|p| bincode::deserialize::<VersionedTransaction>(p.data(..)?).ok())
-            .filter_map(|tx| SanitizedVersionedTransaction::try_from(tx).ok())
         {
-            let (priority, requested_cus) = get_priority_and_requested_cus(&tx);
-            let hash = tx.get_message().message.hash();
-            let Ok(tx) =
-                SanitizedTransaction::try_new(tx, hash, false, &self.alt_store, &HashSet::new())
-            else {
-                eprintln!(
-                    "failed to sanitize transaction. Possibly need to update the alt-store first."
-                );
-                continue;
+            let (tx, compute_budget_limits) = match decode_and_resolve(packet, &self.alt_store) {
+                Ok(decoded) => decoded,
+                Err(failure) => {
+                    decode_failures.record(packet.meta().addr, failure);
+                    continue;
+                }
             };
 
+            let priority = compute_budget_limits.compute_unit_price;
+            let requested_cus = u64::from(compute_budget_limits.compute_unit_limit);
+            let fee = transaction_fee(
+                tx.signatures().len(),
+                tx.message().program_instructions_iter(),
+                &compute_budget_limits,
+            )
+            .total();
             let account_locks = tx.get_account_locks_unchecked();
             for account in &account_locks.writable {
                 let statistics = account_usage_statistics
                     .entry(**account)
                     .or_insert_with(|| AccountUsageStatistics::new(**account));
-                statistics.update(true, priority, requested_cus);
+                statistics.update(true, priority, requested_cus, fee);
             }
             for account in &account_locks.readonly {
                 let statistics = account_usage_statistics
                     .entry(**account)
                     .or_insert_with(|| AccountUsageStatistics::new(**account));
-                statistics.update(false, priority, requested_cus);
+                statistics.update(false, priority, requested_cus, fee);
             }
         }
 
@@ -109,6 +116,8 @@ impl AccountUsageHandler {
         for s in account_usage_statistics {
             AccountUsageStatistics::report(s);
         }
+
+        decode_failures.report(REPORTED_IP_COUNT);
     }
 
     fn handle_packet_batches(&mut self, label: ChannelLabel, packet_batches: BankingPacketBatch) {
@@ -117,14 +126,15 @@ impl AccountUsageHandler {
         }
     }
 
-    fn handle_block_and_bank_hash(&mut self, slot: Slot) {
+    fn handle_block_and_bank_hash(&mut self, slot: Slot) -> ControlFlow<()> {
         if !self.range.contains(&slot) {
             if slot > *self.range.end() {
-                self.done = true;
+                return ControlFlow::Break(());
             }
         } else {
             self.current_packet_batches.clear();
         }
+        ControlFlow::Continue(())
     }
 }
 
@@ -144,6 +154,11 @@ struct AccountUsageStatistics {
     min_requested_cus: u64,
     sum_requested_cus: u64,
     max_requested_cus: u64,
+
+    // Fee in lamports
+    min_fee: u64,
+    sum_fee: u64,
+    max_fee: u64,
 }
 
 impl AccountUsageStatistics {
@@ -158,10 +173,13 @@ impl AccountUsageStatistics {
             min_requested_cus: u64::MAX,
             sum_requested_cus: 0,
             max_requested_cus: 0,
+            min_fee: u64::MAX,
+            sum_fee: 0,
+            max_fee: 0,
         }
     }
 
-    pub fn update(&mut self, is_write: bool, priority: u64, requested_cus: u64) {
+    pub fn update(&mut self, is_write: bool, priority: u64, requested_cus: u64, fee: u64) {
         if is_write {
             self.num_writes += 1;
         } else {
@@ -175,6 +193,10 @@ impl AccountUsageStatistics {
         self.min_requested_cus = self.min_requested_cus.min(requested_cus);
         self.sum_requested_cus += requested_cus;
         self.max_requested_cus = self.max_requested_cus.max(requested_cus);
+
+        self.min_fee = self.min_fee.min(fee);
+        self.sum_fee += fee;
+        self.max_fee = self.max_fee.max(fee);
     }
 
     pub fn report(
@@ -188,43 +210,15 @@ impl AccountUsageStatistics {
             min_requested_cus,
             sum_requested_cus,
             max_requested_cus,
+            min_fee,
+            sum_fee,
+            max_fee,
         }: &Self,
     ) {
         let num_txs = num_reads + num_writes;
         let avg_priority = sum_priority / num_txs as u64;
         let avg_requested_cus = sum_requested_cus / num_txs as u64;
-        println!("{key}: [{num_reads}, {num_writes}] priority: [{min_priority}, {avg_priority}, {max_priority}] requested_cus: [{min_requested_cus}, {avg_requested_cus}, {max_requested_cus}]")
+        let avg_fee = sum_fee / num_txs as u64;
+        println!("{key}: [{num_reads}, {num_writes}] priority: [{min_priority}, {avg_priority}, {max_priority}] requested_cus: [{min_requested_cus}, {avg_requested_cus}, {max_requested_cus}] fee: [{min_fee}, {avg_fee}, {max_fee}]")
     }
 }
-
-/// Returns priorty and requested_cus
-fn get_priority_and_requested_cus(tx: &SanitizedVersionedTransaction) -> (u64, u64) {
-    let instructions = tx.get_message().program_instructions_iter();
-    let mut non_compute_budget_ix_count = 0u64;
-    let mut priority = 0u64;
-    let mut requested_cus = None;
-    for (program, ix) in instructions {
-        if !compute_budget::check_id(program) {
-            non_compute_budget_ix_count += 1;
-            continue;
-        }
-
-        let ix: ComputeBudgetInstruction = try_from_slice_unchecked(&ix.data).unwrap();
-        match ix {
-            ComputeBudgetInstruction::RequestHeapFrame(_) => {}
-            ComputeBudgetInstruction::SetComputeUnitLimit(units) => {
-                requested_cus = Some(units as u64)
-            }
-            ComputeBudgetInstruction::SetComputeUnitPrice(cu_price) => priority = cu_price,
-            ComputeBudgetInstruction::Unused
-            | ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(_) => {}
-        }
-    }
-
-    (
-        priority,
-        requested_cus
-            .unwrap_or(non_compute_budget_ix_count * 200_000)
-            .max(1_400_000),
-    )
-}
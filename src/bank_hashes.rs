@@ -0,0 +1,184 @@
+use {
+    banking_trace_tool::process::process_event_files,
+    solana_clock::Slot,
+    solana_core::banking_trace::{TimedTracedEvent, TracedEvent},
+    solana_hash::Hash,
+    std::{
+        collections::BTreeMap,
+        fs::File,
+        io::{BufRead, BufReader, BufWriter, Write},
+        ops::ControlFlow,
+        path::{Path, PathBuf},
+    },
+};
+
+/// `(blockhash, bank_hash)` of each slot.
+type BankHashes = BTreeMap<Slot, (Hash, Hash)>;
+
+/// Export the `(slot, blockhash, bank_hash)` tuples of the first trace, and
+/// compare every other trace, and the `reference` CSV if given, against it.
+/// Each trace is given as its event files, and named by the corresponding
+/// entry of `trace_names`.
+pub fn bank_hashes(
+    traces: &[Vec<PathBuf>],
+    trace_names: &[String],
+    reference: Option<PathBuf>,
+    output: Option<PathBuf>,
+) -> std::io::Result<()> {
+    let trace_bank_hashes = traces
+        .iter()
+        .zip(trace_names)
+        .map(|(event_file_paths, name)| read_trace_bank_hashes(event_file_paths, name))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let is_comparison = traces.len() > 1 || reference.is_some();
+    match output {
+        Some(output) => write_csv(BufWriter::new(File::create(output)?), &trace_bank_hashes[0])?,
+        None if !is_comparison => write_csv(std::io::stdout().lock(), &trace_bank_hashes[0])?,
+        None => {}
+    }
+
+    let mut num_divergent = 0;
+    for (name, bank_hashes) in trace_names.iter().zip(&trace_bank_hashes).skip(1) {
+        num_divergent += usize::from(!compare(
+            &trace_names[0],
+            &trace_bank_hashes[0],
+            name,
+            bank_hashes,
+        ));
+    }
+    if let Some(reference) = reference {
+        let reference_bank_hashes = read_csv(&reference)?;
+        let reference_name = reference.display().to_string();
+        for (name, bank_hashes) in trace_names.iter().zip(&trace_bank_hashes) {
+            num_divergent += usize::from(!compare(
+                &reference_name,
+                &reference_bank_hashes,
+                name,
+                bank_hashes,
+            ));
+        }
+    }
+
+    if num_divergent > 0 {
+        return Err(std::io::Error::other(format!(
+            "{num_divergent} comparisons found diverging bank hashes"
+        )));
+    }
+    Ok(())
+}
+
+fn read_trace_bank_hashes(event_file_paths: &[PathBuf], name: &str) -> std::io::Result<BankHashes> {
+    let mut handler = BankHashesHandler::default();
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    for slot in &handler.conflicting_slots {
+        eprintln!("Warning: slot {slot} recorded with different hashes in {name}, using the last");
+    }
+    Ok(handler.bank_hashes)
+}
+
+#[derive(Default)]
+struct BankHashesHandler {
+    bank_hashes: BankHashes,
+    /// Slots recorded more than once with different hashes.
+    conflicting_slots: Vec<Slot>,
+}
+
+impl BankHashesHandler {
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        if let TracedEvent::BlockAndBankHash(slot, blockhash, bank_hash) = event {
+            let previous = self.bank_hashes.insert(slot, (blockhash, bank_hash));
+            if previous.is_some_and(|previous| previous != (blockhash, bank_hash)) {
+                self.conflicting_slots.push(slot);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Report slots recorded in only one of the two sources, and the first slot
+/// where their hashes disagree. Returns whether they agree on every common
+/// slot.
+fn compare(name_a: &str, a: &BankHashes, name_b: &str, b: &BankHashes) -> bool {
+    let num_common = a.keys().filter(|slot| b.contains_key(slot)).count();
+    println!(
+        "{name_a} vs {name_b}: {num_common} common slots, {} only in {name_a}, {} only in {name_b}",
+        a.len() - num_common,
+        b.len() - num_common,
+    );
+
+    let divergent_slots: Vec<_> = a
+        .iter()
+        .filter_map(|(slot, hashes_a)| {
+            let hashes_b = b.get(slot)?;
+            (hashes_a != hashes_b).then_some((*slot, hashes_a, hashes_b))
+        })
+        .collect();
+    let Some((slot, (blockhash_a, bank_hash_a), (blockhash_b, bank_hash_b))) =
+        divergent_slots.first()
+    else {
+        println!("  no divergence");
+        return true;
+    };
+
+    println!("  first divergence at slot {slot}:");
+    if blockhash_a != blockhash_b {
+        println!("    blockhash {blockhash_a} vs {blockhash_b}");
+    }
+    if bank_hash_a != bank_hash_b {
+        println!("    bank_hash {bank_hash_a} vs {bank_hash_b}");
+    }
+    println!("  {} diverging slots", divergent_slots.len());
+    false
+}
+
+fn write_csv(mut writer: impl Write, bank_hashes: &BankHashes) -> std::io::Result<()> {
+    writeln!(writer, "slot,blockhash,bank_hash")?;
+    for (slot, (blockhash, bank_hash)) in bank_hashes {
+        writeln!(writer, "{slot},{blockhash},{bank_hash}")?;
+    }
+    writer.flush()
+}
+
+/// Read `slot,blockhash,bank_hash` rows, as written by `write_csv`. A header
+/// row is optional.
+fn read_csv(path: &Path) -> std::io::Result<BankHashes> {
+    let invalid_row = |line_number: usize, reason: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}:{}: {reason}", path.display(), line_number + 1),
+        )
+    };
+
+    let mut bank_hashes = BankHashes::new();
+    for (line_number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        let [slot, blockhash, bank_hash] = fields[..] else {
+            return Err(invalid_row(
+                line_number,
+                "expected slot,blockhash,bank_hash",
+            ));
+        };
+        let Ok(slot) = slot.parse::<Slot>() else {
+            if line_number == 0 {
+                continue; // header
+            }
+            return Err(invalid_row(line_number, "invalid slot"));
+        };
+        let blockhash = blockhash
+            .parse()
+            .map_err(|_| invalid_row(line_number, "invalid blockhash"))?;
+        let bank_hash = bank_hash
+            .parse()
+            .map_err(|_| invalid_row(line_number, "invalid bank hash"))?;
+        bank_hashes.insert(slot, (blockhash, bank_hash));
+    }
+    Ok(bank_hashes)
+}
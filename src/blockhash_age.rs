@@ -0,0 +1,193 @@
+use {
+    crate::{
+        decode::{decode_packet, DecodeFailureCounts},
+        stats::percentile,
+    },
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::process::process_event_files,
+    solana_address::Address,
+    solana_clock::Slot,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_hash::Hash,
+    std::{collections::HashMap, net::IpAddr, ops::ControlFlow, path::PathBuf},
+};
+
+/// Blockhashes older than this many slots can no longer be used.
+pub const MAX_PROCESSING_AGE: u64 = 150;
+/// Width of the age histogram buckets, in slots.
+const AGE_BUCKET_SLOTS: u64 = 25;
+
+/// Report how old the recent blockhash of each non-vote transaction was when
+/// it arrived, and which IPs and fee payers send expired or unknown
+/// blockhashes.
+///
+/// A transaction arrives in the slot after the last `BlockAndBankHash`, and
+/// its blockhash is mapped to the slot of the earlier `BlockAndBankHash` that
+/// recorded it. Only slots built by this node are recorded, so blockhashes
+/// from other leaders' slots are reported as unknown.
+pub fn blockhash_age(
+    event_file_paths: &[PathBuf],
+    ip_count: usize,
+    account_count: usize,
+) -> std::io::Result<()> {
+    let mut handler = BlockhashAgeHandler::default();
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    handler.report(ip_count, account_count);
+    Ok(())
+}
+
+#[derive(Default)]
+struct BlockhashAgeHandler {
+    /// Slot after the last `BlockAndBankHash`.
+    current_slot: Option<Slot>,
+    blockhash_slots: HashMap<Hash, Slot>,
+
+    /// Age in slots of each transaction with a known blockhash.
+    ages: Vec<u64>,
+    totals: AgeCounts,
+    ip_counts: HashMap<IpAddr, AgeCounts>,
+    fee_payer_counts: HashMap<Address, AgeCounts>,
+    decode_failures: DecodeFailureCounts,
+}
+
+#[derive(Clone, Copy, Default)]
+struct AgeCounts {
+    transactions: usize,
+    expired: usize,
+    unknown: usize,
+    sum_age: u64,
+}
+
+impl AgeCounts {
+    fn record(&mut self, age: Option<u64>) {
+        self.transactions += 1;
+        match age {
+            Some(age) => {
+                self.sum_age += age;
+                self.expired += usize::from(age > MAX_PROCESSING_AGE);
+            }
+            None => self.unknown += 1,
+        }
+    }
+
+    fn mean_age(&self) -> Option<f64> {
+        let known = self.transactions - self.unknown;
+        (known > 0).then(|| self.sum_age as f64 / known as f64)
+    }
+}
+
+impl std::fmt::Display for AgeCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "transactions={} expired={} unknown={} mean_age={}",
+            self.transactions,
+            self.expired,
+            self.unknown,
+            self.mean_age()
+                .map_or("-".to_string(), |age| format!("{age:.1}")),
+        )
+    }
+}
+
+impl BlockhashAgeHandler {
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                self.handle_packet_batches(label, packet_batches)
+            }
+            TracedEvent::BlockAndBankHash(slot, blockhash, _) => {
+                self.blockhash_slots.insert(blockhash, slot);
+                self.current_slot = Some(slot + 1);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn handle_packet_batches(&mut self, label: ChannelLabel, packet_batches: BankingPacketBatch) {
+        if !matches!(label, ChannelLabel::NonVote) {
+            return;
+        }
+
+        for packet in packet_batches.iter().flat_map(|b| b.iter()) {
+            let transaction = match decode_packet(packet) {
+                Ok(transaction) => transaction,
+                Err(failure) => {
+                    self.decode_failures.record(packet.meta().addr, failure);
+                    continue;
+                }
+            };
+            let message = &transaction.get_message().message;
+            let age = self
+                .current_slot
+                .zip(self.blockhash_slots.get(message.recent_blockhash()))
+                .map(|(current_slot, blockhash_slot)| current_slot.saturating_sub(*blockhash_slot));
+
+            self.ages.extend(age);
+            self.totals.record(age);
+            self.ip_counts
+                .entry(packet.meta().addr)
+                .or_default()
+                .record(age);
+            if let Some(fee_payer) = message.static_account_keys().first() {
+                self.fee_payer_counts
+                    .entry(*fee_payer)
+                    .or_default()
+                    .record(age);
+            }
+        }
+    }
+
+    pub fn report(&mut self, ip_count: usize, account_count: usize) {
+        println!("Non-vote transactions: {}", self.totals);
+        self.ages.sort_unstable();
+        let format = |value: Option<u64>| value.map_or("-".to_string(), |v| v.to_string());
+        println!(
+            "  age (slots): p50={} p90={} p99={} max={}",
+            format(percentile(&self.ages, 50.0)),
+            format(percentile(&self.ages, 90.0)),
+            format(percentile(&self.ages, 99.0)),
+            format(self.ages.last().copied()),
+        );
+        let mut start = 0;
+        while start <= MAX_PROCESSING_AGE {
+            let end = (start + AGE_BUCKET_SLOTS).min(MAX_PROCESSING_AGE + 1);
+            let count = self
+                .ages
+                .iter()
+                .filter(|age| (start..end).contains(*age))
+                .count();
+            println!("  {start:>4}-{:<4} {count}", end - 1);
+            start = end;
+        }
+        println!(
+            "  expired ({}+) {}",
+            MAX_PROCESSING_AGE + 1,
+            self.totals.expired
+        );
+
+        println!("Top {ip_count} IPs by expired or unknown blockhashes:");
+        for (ip, counts) in top_by_stale(&self.ip_counts, ip_count) {
+            println!("  {ip}: {counts}");
+        }
+        println!("Top {account_count} fee payers by expired or unknown blockhashes:");
+        for (fee_payer, counts) in top_by_stale(&self.fee_payer_counts, account_count) {
+            println!("  {fee_payer}: {counts}");
+        }
+
+        self.decode_failures.report(ip_count);
+    }
+}
+
+/// The `count` keys with the most expired or unknown blockhashes.
+fn top_by_stale<K>(
+    counts: &HashMap<K, AgeCounts>,
+    count: usize,
+) -> impl Iterator<Item = (&K, &AgeCounts)> {
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.expired + counts.unknown));
+    counts.into_iter().take(count)
+}
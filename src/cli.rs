@@ -1,5 +1,7 @@
 use {
-    clap::{Args, Parser, Subcommand},
+    crate::cost_model::{MAX_BLOCK_UNITS, MAX_WRITABLE_ACCOUNT_UNITS},
+    banking_trace_tool::trace_writer::DEFAULT_MAX_EVENT_FILE_SIZE,
+    clap::{builder::RangedU64ValueParser, Args, Parser, Subcommand, ValueEnum},
     solana_address::Address,
     solana_clock::Slot,
     std::{net::IpAddr, path::PathBuf},
@@ -7,9 +9,13 @@ use {
 
 #[derive(Debug, Parser)]
 pub struct Cli {
-    /// The path to the banking trace event file directory.
-    #[clap(short, long)]
-    pub path: PathBuf,
+    /// The path to the banking trace event file directory, or to a tar archive
+    /// of one (optionally gzip or zstd compressed). Archives are extracted to the
+    /// temp directory once, and reused by later runs.
+    /// May be repeated for `bank-hashes`, `first-seen` and `index`, to work on traces
+    /// from several validators at once.
+    #[clap(short, long, required = true)]
+    pub path: Vec<PathBuf>,
     /// Mode to run the trace-tool in.
     #[command(subcommand)]
     pub mode: TraceToolMode,
@@ -19,6 +25,27 @@ pub struct Cli {
 pub enum TraceToolMode {
     /// Get account usage statistics for a given slot range.
     AccountUsage(SlotRange),
+    /// Export the blockhash and bank hash of each slot, or compare them to find where traces
+    /// diverge. With several `--path`s, each trace is compared against the first.
+    BankHashes {
+        /// Also compare each trace against a CSV of slot,blockhash,bank_hash rows.
+        #[clap(long)]
+        reference: Option<PathBuf>,
+        /// Write the hashes of the first trace to this CSV file. Without a comparison, they
+        /// are printed instead.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Report the age of each transaction's recent blockhash when it arrived, and which IPs
+    /// and fee payers send expired or unknown blockhashes.
+    BlockhashAge {
+        /// Number of IPs to show data for.
+        #[clap(short, long, default_value_t = 20)]
+        ip_count: usize,
+        /// Number of fee payers to show data for.
+        #[clap(short, long, default_value_t = 20)]
+        account_count: usize,
+    },
     /// Dump all the non-vote events in the directory.
     Dump {
         /// Limit dumping to these accounts, if specified.
@@ -40,6 +67,16 @@ pub enum TraceToolMode {
         /// Example: "2024-02-02T20:01:30.436991968Z".
         #[clap(long)]
         end_timestamp: Option<String>,
+        /// Keep reading new events as they are written to a live trace directory.
+        #[clap(short, long)]
+        follow: bool,
+    },
+    /// Report which node saw each non-vote transaction signature first, and by how much.
+    /// Pass one `--path` per node.
+    FirstSeen {
+        /// Write a CSV row per signature to this file.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
     },
     /// Write graphia json input file for a given slot.
     GraphiaInput {
@@ -49,6 +86,8 @@ pub enum TraceToolMode {
         #[clap(default_value = "graphia_input.json")]
         output: PathBuf,
     },
+    /// Write an index of slot and timestamp offsets, used to skip ahead in other commands.
+    Index,
     /// Get summary of packet counts.
     PacketCount {
         /// Timestamp to start summary from.
@@ -64,13 +103,173 @@ pub enum TraceToolMode {
         /// Number of IPs to show data for.
         #[clap(short, long)]
         ip_count: Option<usize>,
+        /// Keep reading new events as they are written to a live trace directory.
+        #[clap(short, long)]
+        follow: bool,
+        /// Print a report and reset the counts every this many slots.
+        /// Defaults to every leader window when following.
+        #[clap(long)]
+        report_slots: Option<u64>,
+        /// Write packet counts per time bucket to this file.
+        #[clap(long)]
+        time_series: Option<PathBuf>,
+        /// Width of each time series bucket, in milliseconds.
+        #[clap(long, default_value_t = 400, value_parser = clap::value_parser!(u64).range(1..))]
+        bucket_ms: u64,
+        /// Format of the time series file.
+        #[clap(long, value_enum, default_value_t = TimeSeriesFormat::Csv)]
+        time_series_format: TimeSeriesFormat,
+    },
+    /// Report compute-unit price percentiles per slot, and per write-locked account, for a
+    /// given slot range.
+    PriorityFees {
+        #[clap(flatten)]
+        slot_range: SlotRange,
+        /// Write the minimum fee of each slot as JSON, shaped like the
+        /// `getRecentPrioritizationFees` RPC result.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Write the fee percentiles per slot and per write-locked account as JSON.
+        #[clap(long)]
+        percentiles_output: Option<PathBuf>,
+        /// Number of write-locked accounts to show data for.
+        #[clap(short, long, default_value_t = 20)]
+        account_count: usize,
+    },
+    /// Get program usage statistics for a given slot range.
+    ProgramUsage(SlotRange),
+    /// Write a copy of the trace with sender IPs replaced by keyed pseudonyms, for sharing.
+    Redact {
+        /// Directory to write the redacted trace to.
+        output: PathBuf,
+        /// File holding the secret key for the IP pseudonyms. The same key always maps an IP
+        /// to the same pseudonym, so traces redacted with the same key can be compared.
+        /// Defaults to the `BANKING_TRACE_REDACT_KEY` environment variable.
+        #[clap(short, long)]
+        key_file: Option<PathBuf>,
+        /// Also set the sender port of every packet to zero.
+        #[clap(long)]
+        zero_port: bool,
+        /// Drop packets with transactions signed by any of these keys.
+        #[clap(long)]
+        strip_signers: Option<Vec<Address>>,
+        /// Size in bytes at which output event files are rotated.
+        #[clap(long, default_value_t = DEFAULT_MAX_EVENT_FILE_SIZE)]
+        max_file_size: u64,
+    },
+    /// Report how often each transaction was resent, from which IPs, and over how long.
+    Resends {
+        /// Write a row per signature, with the arrival slot of each copy, to this CSV file.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Number of IPs to show data for.
+        #[clap(short, long, default_value_t = 20)]
+        ip_count: usize,
+    },
+    /// Greedily pack the non-vote transactions received during a slot into a block, highest
+    /// priority first, under the block, account and vote CU limits.
+    SimulateBlock {
+        /// The slot to simulate the block for.
+        slot: Slot,
+        /// Maximum CUs in the block.
+        #[clap(long, default_value_t = MAX_BLOCK_UNITS)]
+        block_cu_limit: u64,
+        /// Maximum CUs of transactions write-locking any one account.
+        #[clap(long, default_value_t = MAX_WRITABLE_ACCOUNT_UNITS)]
+        account_cu_limit: u64,
+        /// CUs of the block reserved for votes. Defaults to the cost of the unique votes
+        /// received during the slot.
+        #[clap(long)]
+        vote_reservation: Option<u64>,
+        /// Number of left out transactions to show.
+        #[clap(short, long, default_value_t = 20)]
+        skipped_count: usize,
+    },
+    /// Schedule the non-vote transactions received during a slot with a prio-graph onto
+    /// simulated execution threads, and report makespan, thread utilization and parallelism.
+    SimulateScheduler {
+        /// The slot to simulate scheduling for.
+        slot: Slot,
+        /// Number of simulated execution threads.
+        #[clap(
+            short,
+            long,
+            default_value_t = 4,
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        num_threads: usize,
+        /// Maximum number of transactions in a batch sent to a thread.
+        #[clap(
+            short,
+            long,
+            default_value_t = 64,
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        batch_size: usize,
+    },
+    /// Write the events for a slot range, or the packets using given accounts or IPs, to a
+    /// new trace directory.
+    Slice {
+        /// Directory to write the sliced trace to.
+        output: PathBuf,
+        /// First slot to keep. Packets are kept if the next `BlockAndBankHash` after them is in
+        /// the slot range. Packets after the last one are only kept if their slot must be in
+        /// range.
+        #[clap(long)]
+        start_slot: Option<Slot>,
+        /// Last slot to keep, inclusive.
+        #[clap(long)]
+        end_slot: Option<Slot>,
+        /// Only keep packets using these accounts, if specified.
+        #[clap(short, long)]
+        accounts: Option<Vec<Address>>,
+        /// Only keep packets from these IPs, if specified.
+        #[clap(short, long)]
+        ips: Option<Vec<IpAddr>>,
+        /// Skip ALT resolution when matching accounts.
+        #[clap(short, long)]
+        skip_alt_resolution: bool,
+        /// Size in bytes at which output event files are rotated.
+        #[clap(long, default_value_t = DEFAULT_MAX_EVENT_FILE_SIZE)]
+        max_file_size: u64,
     },
     /// Get the ranges of slots for data in directory.
     SlotRanges,
+    /// Print a row per slot with its hashes, timing, packet counts per channel, and fees offered.
+    SlotSummary {
+        /// Print CSV instead of a table.
+        #[clap(long)]
+        csv: bool,
+    },
+    /// Report slot and leader window durations, and gaps between leader windows.
+    SlotTiming {
+        /// Flag slots that took longer than this many milliseconds.
+        #[clap(long, default_value_t = 400)]
+        target_ms: u64,
+    },
     /// Get the time ranges of data in the directory.
     TimeRange,
     /// Update Address-Lookup-Table store for tables used in a given slot-range.
     UpdateAltStore(SlotRange),
+    /// Check event files for undecodable records.
+    Verify {
+        /// Resync after an undecodable record and keep checking the rest of the file.
+        #[clap(short, long)]
+        keep_going: bool,
+    },
+    /// Get vote statistics for the TPU and gossip vote channels.
+    VoteStats {
+        /// Number of vote accounts to show data for.
+        #[clap(short, long, default_value_t = 20)]
+        account_count: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TimeSeriesFormat {
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
 }
 
 #[derive(Debug, Args)]
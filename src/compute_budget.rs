@@ -0,0 +1,359 @@
+use {
+    solana_address::Address,
+    solana_borsh::v1::try_from_slice_unchecked,
+    solana_compute_budget_interface::ComputeBudgetInstruction,
+    solana_sdk_ids::{
+        bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable, compute_budget, ed25519_program,
+        loader_v4, secp256k1_program, secp256r1_program, system_program, vote,
+        zk_elgamal_proof_program,
+    },
+    solana_transaction::versioned::sanitized::SanitizedVersionedTransaction,
+};
+
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Default compute units of an instruction of a non-builtin program.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Default compute units of an instruction of a builtin program.
+pub const MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT: u32 = 3_000;
+pub const MIN_HEAP_FRAME_BYTES: u32 = 32 * 1024;
+pub const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+const HEAP_FRAME_BYTES_GRANULARITY: u32 = 1024;
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: u32 = 64 * 1024 * 1024;
+
+/// Builtin programs, which default to `MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT`
+/// per instruction. The stake, config and address lookup table programs have
+/// been migrated to BPF, so they take the default of other programs.
+const BUILTIN_PROGRAM_IDS: [Address; 11] = [
+    system_program::ID,
+    vote::ID,
+    bpf_loader_deprecated::ID,
+    bpf_loader::ID,
+    bpf_loader_upgradeable::ID,
+    loader_v4::ID,
+    compute_budget::ID,
+    ed25519_program::ID,
+    secp256k1_program::ID,
+    secp256r1_program::ID,
+    zk_elgamal_proof_program::ID,
+];
+
+/// Compute budget of a transaction, as agave derives it from the transaction's
+/// compute budget instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComputeBudgetLimits {
+    pub compute_unit_limit: u32,
+    /// Price per compute unit, in micro-lamports.
+    pub compute_unit_price: u64,
+    pub heap_frame_bytes: u32,
+    pub loaded_accounts_data_size_limit: u32,
+}
+
+/// Why agave would reject a transaction's compute budget instructions. Each
+/// variant holds the index of the offending instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputeBudgetError {
+    DuplicateInstruction(u8),
+    InvalidInstructionData(u8),
+    InvalidLoadedAccountsDataSizeLimit(u8),
+}
+
+/// Derive the compute budget of `transaction`:
+/// - Each kind of compute budget instruction may appear at most once.
+/// - Without a compute unit limit instruction, each builtin instruction gets
+///   `MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT` and every other instruction
+///   gets `DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT`.
+/// - The compute unit limit is clamped to `MAX_COMPUTE_UNIT_LIMIT`.
+/// - A requested heap frame must be a multiple of 1KiB between
+///   `MIN_HEAP_FRAME_BYTES` and `MAX_HEAP_FRAME_BYTES`.
+/// - A loaded accounts data size limit must be non-zero, and is clamped to
+///   `MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES`.
+pub fn compute_budget_limits(
+    transaction: &SanitizedVersionedTransaction,
+) -> Result<ComputeBudgetLimits, ComputeBudgetError> {
+    let mut compute_unit_limit = None;
+    let mut compute_unit_price = None;
+    let mut heap_frame_bytes = None;
+    let mut loaded_accounts_data_size_limit = None;
+    let mut num_builtin_instructions = 0u32;
+    let mut num_non_builtin_instructions = 0u32;
+
+    for (index, (program_id, instruction)) in transaction
+        .get_message()
+        .program_instructions_iter()
+        .enumerate()
+    {
+        // Agave truncates the index the same way in
+        // `ComputeBudgetInstructionDetails::try_from`, and leaves rejecting
+        // transactions with too many instructions to sanitization.
+        let index = index as u8;
+        if BUILTIN_PROGRAM_IDS.contains(program_id) {
+            num_builtin_instructions += 1;
+        } else {
+            num_non_builtin_instructions += 1;
+        }
+        if !compute_budget::check_id(program_id) {
+            continue;
+        }
+
+        match try_from_slice_unchecked(&instruction.data) {
+            Ok(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                set_once(&mut heap_frame_bytes, bytes, index)?
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                set_once(&mut compute_unit_limit, units, index)?
+            }
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => {
+                set_once(&mut compute_unit_price, price, index)?
+            }
+            Ok(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
+                set_once(&mut loaded_accounts_data_size_limit, bytes, index)?
+            }
+            Ok(ComputeBudgetInstruction::Unused) | Err(_) => {
+                return Err(ComputeBudgetError::InvalidInstructionData(index))
+            }
+        }
+    }
+
+    let heap_frame_bytes = match heap_frame_bytes {
+        Some((bytes, index)) => {
+            if !(MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes)
+                || bytes % HEAP_FRAME_BYTES_GRANULARITY != 0
+            {
+                return Err(ComputeBudgetError::InvalidInstructionData(index));
+            }
+            bytes
+        }
+        None => MIN_HEAP_FRAME_BYTES,
+    };
+    let loaded_accounts_data_size_limit = match loaded_accounts_data_size_limit {
+        Some((0, index)) => {
+            return Err(ComputeBudgetError::InvalidLoadedAccountsDataSizeLimit(
+                index,
+            ))
+        }
+        Some((bytes, _)) => bytes.min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+        None => MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+    };
+    let compute_unit_limit = compute_unit_limit
+        .map(|(units, _)| units)
+        .unwrap_or_else(|| {
+            num_builtin_instructions
+                .saturating_mul(MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT)
+                .saturating_add(
+                    num_non_builtin_instructions
+                        .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT),
+                )
+        })
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+
+    Ok(ComputeBudgetLimits {
+        compute_unit_limit,
+        compute_unit_price: compute_unit_price.map_or(0, |(price, _)| price),
+        heap_frame_bytes,
+        loaded_accounts_data_size_limit,
+    })
+}
+
+/// Record the value of a compute budget instruction with its instruction
+/// index, unless that kind of instruction was already seen.
+fn set_once<T>(
+    value: &mut Option<(T, u8)>,
+    new_value: T,
+    index: u8,
+) -> Result<(), ComputeBudgetError> {
+    if value.is_some() {
+        return Err(ComputeBudgetError::DuplicateInstruction(index));
+    }
+    *value = Some((new_value, index));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_message::{CompiledInstruction, Message, MessageHeader, VersionedMessage},
+        solana_signature::Signature,
+        solana_transaction::versioned::VersionedTransaction,
+    };
+
+    const FEE_PAYER: Address = Address::new_from_array([1; 32]);
+    const NON_BUILTIN_PROGRAM: Address = Address::new_from_array([2; 32]);
+
+    /// A transaction calling `program_ids` in order, with the compute budget
+    /// instruction data in `compute_budget_data` appended.
+    fn transaction(
+        program_ids: &[Address],
+        compute_budget_data: &[Vec<u8>],
+    ) -> SanitizedVersionedTransaction {
+        let account_keys = vec![
+            FEE_PAYER,
+            compute_budget::ID,
+            system_program::ID,
+            NON_BUILTIN_PROGRAM,
+        ];
+        let program_id_index = |program_id: &Address| {
+            account_keys
+                .iter()
+                .position(|key| key == program_id)
+                .unwrap() as u8
+        };
+        let instructions = program_ids
+            .iter()
+            .map(|program_id| (program_id_index(program_id), Vec::new()))
+            .chain(
+                compute_budget_data
+                    .iter()
+                    .map(|data| (program_id_index(&compute_budget::ID), data.clone())),
+            )
+            .map(|(program_id_index, data)| CompiledInstruction {
+                program_id_index,
+                accounts: Vec::new(),
+                data,
+            })
+            .collect();
+        let message = Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 3,
+            },
+            account_keys,
+            recent_blockhash: Hash::default(),
+            instructions,
+        };
+        SanitizedVersionedTransaction::try_from(VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(message),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_builtin_defaults() {
+        let limits = compute_budget_limits(&transaction(
+            &[system_program::ID, NON_BUILTIN_PROGRAM],
+            &[],
+        ))
+        .unwrap();
+        assert_eq!(
+            limits,
+            ComputeBudgetLimits {
+                compute_unit_limit: MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT
+                    + DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+                compute_unit_price: 0,
+                heap_frame_bytes: MIN_HEAP_FRAME_BYTES,
+                loaded_accounts_data_size_limit: MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+            }
+        );
+
+        // The compute budget instruction is a builtin too.
+        let limits = compute_budget_limits(&transaction(
+            &[NON_BUILTIN_PROGRAM],
+            &[ComputeBudgetInstruction::set_compute_unit_price(7).data],
+        ))
+        .unwrap();
+        assert_eq!(
+            limits.compute_unit_limit,
+            DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT + MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(limits.compute_unit_price, 7);
+
+        // Defaults are clamped to the maximum too.
+        let limits = compute_budget_limits(&transaction(&[NON_BUILTIN_PROGRAM; 8], &[])).unwrap();
+        assert_eq!(limits.compute_unit_limit, MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn test_compute_unit_limit() {
+        let limits = compute_budget_limits(&transaction(
+            &[NON_BUILTIN_PROGRAM],
+            &[ComputeBudgetInstruction::set_compute_unit_limit(1_000).data],
+        ))
+        .unwrap();
+        assert_eq!(limits.compute_unit_limit, 1_000);
+
+        let limits = compute_budget_limits(&transaction(
+            &[NON_BUILTIN_PROGRAM],
+            &[ComputeBudgetInstruction::set_compute_unit_limit(u32::MAX).data],
+        ))
+        .unwrap();
+        assert_eq!(limits.compute_unit_limit, MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn test_duplicate_instructions() {
+        let result = compute_budget_limits(&transaction(
+            &[NON_BUILTIN_PROGRAM],
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(1).data,
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000).data,
+                ComputeBudgetInstruction::set_compute_unit_price(2).data,
+            ],
+        ));
+        assert_eq!(result, Err(ComputeBudgetError::DuplicateInstruction(3)));
+    }
+
+    #[test]
+    fn test_heap_frame_bounds() {
+        let heap_frame_result = |bytes| {
+            compute_budget_limits(&transaction(
+                &[],
+                &[ComputeBudgetInstruction::request_heap_frame(bytes).data],
+            ))
+            .map(|limits| limits.heap_frame_bytes)
+        };
+        assert_eq!(
+            heap_frame_result(MIN_HEAP_FRAME_BYTES),
+            Ok(MIN_HEAP_FRAME_BYTES)
+        );
+        assert_eq!(
+            heap_frame_result(MAX_HEAP_FRAME_BYTES),
+            Ok(MAX_HEAP_FRAME_BYTES)
+        );
+        assert_eq!(heap_frame_result(64 * 1024), Ok(64 * 1024));
+        for bytes in [
+            MIN_HEAP_FRAME_BYTES - HEAP_FRAME_BYTES_GRANULARITY,
+            MAX_HEAP_FRAME_BYTES + HEAP_FRAME_BYTES_GRANULARITY,
+            MIN_HEAP_FRAME_BYTES + 1,
+        ] {
+            assert_eq!(
+                heap_frame_result(bytes),
+                Err(ComputeBudgetError::InvalidInstructionData(0))
+            );
+        }
+    }
+
+    #[test]
+    fn test_loaded_accounts_data_size_limit() {
+        let data_size_result = |bytes| {
+            compute_budget_limits(&transaction(
+                &[],
+                &[ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(bytes).data],
+            ))
+            .map(|limits| limits.loaded_accounts_data_size_limit)
+        };
+        assert_eq!(
+            data_size_result(0),
+            Err(ComputeBudgetError::InvalidLoadedAccountsDataSizeLimit(0))
+        );
+        assert_eq!(data_size_result(1024), Ok(1024));
+        assert_eq!(
+            data_size_result(u32::MAX),
+            Ok(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES)
+        );
+    }
+
+    #[test]
+    fn test_invalid_instruction_data() {
+        let result = compute_budget_limits(&transaction(&[], &[vec![], vec![0xff]]));
+        assert_eq!(result, Err(ComputeBudgetError::InvalidInstructionData(0)));
+    }
+
+    #[test]
+    fn test_instruction_index_past_u8() {
+        let result = compute_budget_limits(&transaction(&[system_program::ID; 257], &[vec![]]));
+        assert_eq!(result, Err(ComputeBudgetError::InvalidInstructionData(1)));
+    }
+}
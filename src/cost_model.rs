@@ -0,0 +1,75 @@
+use {
+    crate::compute_budget::ComputeBudgetLimits,
+    solana_sdk_ids::{ed25519_program, secp256k1_program, secp256r1_program},
+    solana_transaction::sanitized::SanitizedTransaction,
+};
+
+pub const SIGNATURE_COST: u64 = 720;
+pub const SECP256K1_VERIFY_COST: u64 = 6_690;
+pub const ED25519_VERIFY_COST: u64 = 2_280;
+pub const SECP256R1_VERIFY_COST: u64 = 4_800;
+pub const WRITE_LOCK_UNITS: u64 = 300;
+/// Bytes of instruction data per compute unit.
+pub const INSTRUCTION_DATA_BYTES_COST: u64 = 4;
+const ACCOUNT_DATA_COST_PAGE_SIZE: u64 = 32 * 1024;
+const ACCOUNT_DATA_COST_PER_PAGE: u64 = 8;
+
+pub const MAX_BLOCK_UNITS: u64 = 60_000_000;
+pub const MAX_WRITABLE_ACCOUNT_UNITS: u64 = 12_000_000;
+pub const MAX_VOTE_UNITS: u64 = 36_000_000;
+/// Cost of a simple vote transaction.
+pub const SIMPLE_VOTE_USAGE_COST: u64 = 3_428;
+
+/// Cost of a non-vote transaction, following agave's cost model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransactionCost {
+    /// Transaction and precompile signature verification.
+    pub signature_cost: u64,
+    pub write_lock_cost: u64,
+    pub data_bytes_cost: u64,
+    /// The transaction's compute unit limit.
+    pub programs_execution_cost: u64,
+    pub loaded_accounts_data_size_cost: u64,
+}
+
+impl TransactionCost {
+    pub fn sum(&self) -> u64 {
+        self.signature_cost
+            + self.write_lock_cost
+            + self.data_bytes_cost
+            + self.programs_execution_cost
+            + self.loaded_accounts_data_size_cost
+    }
+}
+
+pub fn transaction_cost(
+    transaction: &SanitizedTransaction,
+    compute_budget_limits: &ComputeBudgetLimits,
+) -> TransactionCost {
+    let mut signature_cost = transaction.signatures().len() as u64 * SIGNATURE_COST;
+    let mut data_bytes = 0;
+    for (program_id, instruction) in transaction.message().program_instructions_iter() {
+        data_bytes += instruction.data.len() as u64;
+        let num_signatures = u64::from(instruction.data.first().copied().unwrap_or(0));
+        if ed25519_program::check_id(program_id) {
+            signature_cost += num_signatures * ED25519_VERIFY_COST;
+        } else if secp256k1_program::check_id(program_id) {
+            signature_cost += num_signatures * SECP256K1_VERIFY_COST;
+        } else if secp256r1_program::check_id(program_id) {
+            signature_cost += num_signatures * SECP256R1_VERIFY_COST;
+        }
+    }
+    let num_write_locks = transaction.get_account_locks_unchecked().writable.len() as u64;
+    let loaded_accounts_data_size =
+        u64::from(compute_budget_limits.loaded_accounts_data_size_limit);
+
+    TransactionCost {
+        signature_cost,
+        write_lock_cost: num_write_locks * WRITE_LOCK_UNITS,
+        data_bytes_cost: data_bytes / INSTRUCTION_DATA_BYTES_COST,
+        programs_execution_cost: u64::from(compute_budget_limits.compute_unit_limit),
+        loaded_accounts_data_size_cost: loaded_accounts_data_size
+            .div_ceil(ACCOUNT_DATA_COST_PAGE_SIZE)
+            * ACCOUNT_DATA_COST_PER_PAGE,
+    }
+}
@@ -0,0 +1,180 @@
+use {
+    crate::compute_budget::{compute_budget_limits, ComputeBudgetLimits},
+    solana_alt_store::Store,
+    solana_perf::packet::PacketRef,
+    solana_sanitize::SanitizeError,
+    solana_transaction::{
+        sanitized::SanitizedTransaction,
+        versioned::{sanitized::SanitizedVersionedTransaction, VersionedTransaction},
+    },
+    solana_transaction_error::TransactionError,
+    std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        net::IpAddr,
+    },
+};
+
+/// Number of IPs reported by reports without an IP count option.
+pub const REPORTED_IP_COUNT: usize = 5;
+
+/// Why a packet did not yield a usable transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DecodeFailure {
+    /// Marked as discarded by sigverify.
+    Discarded,
+    EmptyPayload,
+    /// The payload is not a bincode-encoded `VersionedTransaction`.
+    Deserialize,
+    SanitizeIndexOutOfBounds,
+    SanitizeValueOutOfBounds,
+    SanitizeInvalidValue,
+    /// Compute budget instructions that agave would reject.
+    InvalidComputeBudget,
+    /// An address lookup table could not be resolved from the alt-store.
+    AltLookup,
+    /// The transaction could not be resolved for a reason other than its
+    /// address lookup tables.
+    Resolve,
+}
+
+impl DecodeFailure {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Discarded => "sigverify_discard",
+            Self::EmptyPayload => "empty_payload",
+            Self::Deserialize => "bincode_error",
+            Self::SanitizeIndexOutOfBounds => "sanitize_index_out_of_bounds",
+            Self::SanitizeValueOutOfBounds => "sanitize_value_out_of_bounds",
+            Self::SanitizeInvalidValue => "sanitize_invalid_value",
+            Self::InvalidComputeBudget => "invalid_compute_budget",
+            Self::AltLookup => "alt_lookup",
+            Self::Resolve => "resolve_error",
+        }
+    }
+}
+
+impl From<SanitizeError> for DecodeFailure {
+    fn from(err: SanitizeError) -> Self {
+        match err {
+            SanitizeError::IndexOutOfBounds => Self::SanitizeIndexOutOfBounds,
+            SanitizeError::ValueOutOfBounds => Self::SanitizeValueOutOfBounds,
+            SanitizeError::InvalidValue => Self::SanitizeInvalidValue,
+        }
+    }
+}
+
+impl From<TransactionError> for DecodeFailure {
+    fn from(err: TransactionError) -> Self {
+        match err {
+            TransactionError::AddressLookupTableNotFound
+            | TransactionError::InvalidAddressLookupTableOwner
+            | TransactionError::InvalidAddressLookupTableData
+            | TransactionError::InvalidAddressLookupTableIndex => Self::AltLookup,
+            _ => Self::Resolve,
+        }
+    }
+}
+
+/// Decode and sanitize the transaction in `packet`.
+pub fn decode_packet(packet: PacketRef) -> Result<SanitizedVersionedTransaction, DecodeFailure> {
+    Ok(SanitizedVersionedTransaction::try_from(
+        deserialize_packet(packet)?,
+    )?)
+}
+
+/// Decode the transaction in `packet`, without sanitizing it.
+pub fn deserialize_packet(packet: PacketRef) -> Result<VersionedTransaction, DecodeFailure> {
+    if packet.meta().discard() {
+        return Err(DecodeFailure::Discarded);
+    }
+    let data = packet
+        .data(..)
+        .filter(|data| !data.is_empty())
+        .ok_or(DecodeFailure::EmptyPayload)?;
+    bincode::deserialize::<VersionedTransaction>(data).map_err(|_| DecodeFailure::Deserialize)
+}
+
+/// Resolve the address lookup tables of `transaction` from `alt_store`.
+pub fn resolve_transaction(
+    transaction: SanitizedVersionedTransaction,
+    alt_store: &Store,
+) -> Result<SanitizedTransaction, DecodeFailure> {
+    let hash = transaction.get_message().message.hash();
+    Ok(SanitizedTransaction::try_new(
+        transaction,
+        hash,
+        false,
+        alt_store,
+        &HashSet::new(),
+    )?)
+}
+
+/// Decode the transaction in `packet`, derive its compute budget, and resolve
+/// its ALTs.
+pub fn decode_and_resolve(
+    packet: PacketRef,
+    alt_store: &Store,
+) -> Result<(SanitizedTransaction, ComputeBudgetLimits), DecodeFailure> {
+    let transaction = decode_packet(packet)?;
+    let compute_budget_limits =
+        compute_budget_limits(&transaction).map_err(|_| DecodeFailure::InvalidComputeBudget)?;
+    let transaction = resolve_transaction(transaction, alt_store)?;
+    Ok((transaction, compute_budget_limits))
+}
+
+/// Counts of decode failures, in total and per sender IP.
+#[derive(Default)]
+pub struct DecodeFailureCounts {
+    counts: BTreeMap<DecodeFailure, usize>,
+    ip_counts: HashMap<IpAddr, BTreeMap<DecodeFailure, usize>>,
+}
+
+impl DecodeFailureCounts {
+    pub fn record(&mut self, ip: IpAddr, failure: DecodeFailure) {
+        *self.counts.entry(failure).or_default() += 1;
+        *self
+            .ip_counts
+            .entry(ip)
+            .or_default()
+            .entry(failure)
+            .or_default() += 1;
+    }
+
+    pub fn count(&self, failure: DecodeFailure) -> usize {
+        self.counts.get(&failure).copied().unwrap_or_default()
+    }
+
+    /// Print the failure counts, and the `ip_count` IPs with the most failures.
+    /// ALT lookup failures usually mean the alt-store is out of date, so a hint
+    /// to update it is printed along with them.
+    pub fn report(&self, ip_count: usize) {
+        let format_counts = |counts: &BTreeMap<DecodeFailure, usize>| {
+            counts
+                .iter()
+                .map(|(failure, count)| format!(" {}={count}", failure.name()))
+                .collect::<String>()
+        };
+        println!(
+            "Decode failures: {}{}",
+            self.counts.values().sum::<usize>(),
+            format_counts(&self.counts)
+        );
+        if self.count(DecodeFailure::AltLookup) > 0 {
+            eprintln!("failed to resolve ALTs. Possibly need to update the alt-store first.");
+        }
+        if self.ip_counts.is_empty() {
+            return;
+        }
+
+        let mut ip_counts: Vec<_> = self
+            .ip_counts
+            .iter()
+            .map(|(ip, counts)| (ip, counts.values().sum::<usize>(), counts))
+            .collect();
+        ip_counts.sort_by_key(|(_, total, _)| std::cmp::Reverse(*total));
+        println!("Top {ip_count} IPs by decode failures:");
+        for (ip, total, counts) in ip_counts.into_iter().take(ip_count) {
+            println!("  {ip}: total={total}{}", format_counts(counts));
+        }
+    }
+}
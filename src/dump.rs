@@ -1,20 +1,25 @@
 use {
-    crate::process::process_event_files,
+    crate::{
+        decode::{DecodeFailureCounts, REPORTED_IP_COUNT},
+        filter::{FilteredTransaction, PacketFilter},
+    },
     agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{
+        process::{follow_event_files, process_event_files, process_event_files_from},
+        seek_index::SeekTarget,
+    },
     chrono::{DateTime, Utc},
     solana_address::Address,
-    solana_alt_store::Store,
     solana_clock::Slot,
     solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
-    solana_transaction::{
-        sanitized::SanitizedTransaction,
-        versioned::{sanitized::SanitizedVersionedTransaction, VersionedTransaction},
-    },
-    std::{collections::HashSet, net::IpAddr, path::PathBuf},
+    std::{collections::HashSet, net::IpAddr, ops::ControlFlow, path::PathBuf},
 };
 
+/// Dump the non-vote transactions in the trace, or in the live trace directory
+/// `follow` as it is written.
 pub fn dump(
     event_file_paths: &[PathBuf],
+    follow: Option<PathBuf>,
     accounts: Option<HashSet<Address>>,
     ips: Option<HashSet<IpAddr>>,
     skip_alt_resolution: bool,
@@ -28,18 +33,26 @@ pub fn dump(
         start_timestamp,
         end_timestamp,
     );
-    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    let mut handler_fn = |event| handler.handle_event(event);
+    match (follow, start_timestamp) {
+        (Some(directory), _) => follow_event_files(directory, &mut handler_fn)?,
+        (None, Some(start_timestamp)) => process_event_files_from(
+            event_file_paths,
+            SeekTarget::Timestamp(start_timestamp.into()),
+            &mut handler_fn,
+        )?,
+        (None, None) => process_event_files(event_file_paths, &mut handler_fn)?,
+    }
+    handler.decode_failures.report(REPORTED_IP_COUNT);
     Ok(())
 }
 
 struct Dumper {
-    accounts: Option<HashSet<Address>>,
-    ips: Option<HashSet<IpAddr>>,
+    filter: PacketFilter,
+    decode_failures: DecodeFailureCounts,
     start_timestamp: Option<DateTime<Utc>>,
     end_timestamp: Option<DateTime<Utc>>,
-    alt_store: Option<Store>,
     started: bool,
-    done: bool,
 }
 
 impl Dumper {
@@ -50,37 +63,35 @@ impl Dumper {
         start_timestamp: Option<DateTime<Utc>>,
         end_timestamp: Option<DateTime<Utc>>,
     ) -> Self {
-        const ALT_STORE_PATH: &str = "alt-store.bin";
         let started = start_timestamp.is_none();
         Self {
-            accounts,
-            ips,
+            filter: PacketFilter::new(accounts, ips, skip_alt_resolution),
+            decode_failures: DecodeFailureCounts::default(),
             start_timestamp,
             end_timestamp,
-            alt_store: (!skip_alt_resolution)
-                .then(|| Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store")),
             started,
-            done: false,
         }
     }
 
-    pub fn handle_event(&mut self, TimedTracedEvent(timestamp, event): TimedTracedEvent) {
-        if self.done {
-            return;
-        }
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
         let timestamp = DateTime::<Utc>::from(timestamp);
         self.started = self.started
             || self
                 .start_timestamp
                 .map(|start| timestamp >= start)
                 .unwrap_or(true);
-        self.done = self.done
-            || self
-                .end_timestamp
-                .map(|end| timestamp > end)
-                .unwrap_or(false);
+        let done = self
+            .end_timestamp
+            .map(|end| timestamp > end)
+            .unwrap_or(false);
+        if done {
+            return ControlFlow::Break(());
+        }
 
-        if self.started && !self.done {
+        if self.started {
             match event {
                 TracedEvent::PacketBatch(label, packet_batches) => {
                     self.handle_packet_batches(timestamp, label, packet_batches)
@@ -90,6 +101,7 @@ impl Dumper {
                 }
             }
         }
+        ControlFlow::Continue(())
     }
 
     fn handle_packet_batches(
@@ -101,69 +113,16 @@ impl Dumper {
         if matches!(label, ChannelLabel::NonVote) {
             for packet_batch in packet_batches.iter() {
                 for packet in packet_batch {
-                    let Some(data) = packet.data(..) else {
-                        continue;
-                    };
-                    let Some(versioned_transaction) =
-                        bincode::deserialize::<VersionedTransaction>(data).ok()
-                    else {
-                        continue;
-                    };
-                    let Ok(sanitized_versioned_transaction) =
-                        SanitizedVersionedTransaction::try_from(versioned_transaction)
-                    else {
-                        continue;
-                    };
-
-                    if let Some(ips) = &self.ips {
-                        if !ips.contains(&packet.meta().addr) {
-                            continue;
-                        }
-                    }
-
-                    match &self.alt_store {
-                        None => {
-                            // Skipping ALT resolution, check static SVT account keys and dump.
-                            let dump = if let Some(accounts) = &self.accounts {
-                                sanitized_versioned_transaction
-                                    .get_message()
-                                    .message
-                                    .static_account_keys()
-                                    .iter()
-                                    .any(|account| accounts.contains(account))
-                            } else {
-                                true
-                            };
-                            if dump {
-                                println!("{timestamp:?} - {sanitized_versioned_transaction:?}");
-                            }
+                    match self.filter.filter(packet) {
+                        Err(failure) => self.decode_failures.record(packet.meta().addr, failure),
+                        Ok(None) => {}
+                        Ok(Some(FilteredTransaction::Unresolved(
+                            sanitized_versioned_transaction,
+                        ))) => {
+                            println!("{timestamp:?} - {sanitized_versioned_transaction:?}");
                         }
-                        Some(alt_store) => {
-                            // Resolve ALT. If successful, check all account keys and dump.
-                            let hash = sanitized_versioned_transaction.get_message().message.hash();
-                            let Ok(sanitized_transaction) = SanitizedTransaction::try_new(
-                                sanitized_versioned_transaction,
-                                hash,
-                                false,
-                                alt_store,
-                                &HashSet::new(),
-                            ) else {
-                                continue;
-                            };
-
-                            let message = sanitized_transaction.message();
-                            let account_keys = message.account_keys();
-                            let dump = if let Some(accounts) = &self.accounts {
-                                account_keys
-                                    .iter()
-                                    .any(|account| accounts.contains(account))
-                            } else {
-                                true
-                            };
-
-                            if dump {
-                                println!("{timestamp:?} - {sanitized_transaction:?}");
-                            }
+                        Ok(Some(FilteredTransaction::Resolved(sanitized_transaction))) => {
+                            println!("{timestamp:?} - {sanitized_transaction:?}");
                         }
                     }
                 }
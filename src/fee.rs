@@ -0,0 +1,54 @@
+use {
+    crate::compute_budget::ComputeBudgetLimits,
+    solana_address::Address,
+    solana_message::CompiledInstruction,
+    solana_sdk_ids::{ed25519_program, secp256k1_program, secp256r1_program},
+};
+
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// Fee a transaction would pay if it landed, in lamports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransactionFee {
+    /// `LAMPORTS_PER_SIGNATURE` for each transaction and precompile signature.
+    pub base_fee: u64,
+    /// Compute unit price times compute unit limit, rounded up to a lamport.
+    pub prioritization_fee: u64,
+}
+
+impl TransactionFee {
+    pub fn total(&self) -> u64 {
+        self.base_fee.saturating_add(self.prioritization_fee)
+    }
+}
+
+/// Fee of a transaction with `num_transaction_signatures` signatures and the
+/// given top-level instructions. Signatures verified by the ed25519, secp256k1
+/// and secp256r1 precompiles are charged like transaction signatures, with the
+/// count taken from the first byte of the instruction data.
+pub fn transaction_fee<'a>(
+    num_transaction_signatures: usize,
+    instructions: impl Iterator<Item = (&'a Address, &'a CompiledInstruction)>,
+    compute_budget_limits: &ComputeBudgetLimits,
+) -> TransactionFee {
+    let num_precompile_signatures: u64 = instructions
+        .filter(|(program_id, _)| {
+            ed25519_program::check_id(program_id)
+                || secp256k1_program::check_id(program_id)
+                || secp256r1_program::check_id(program_id)
+        })
+        .map(|(_, instruction)| u64::from(instruction.data.first().copied().unwrap_or(0)))
+        .sum();
+    let num_signatures =
+        (num_transaction_signatures as u64).saturating_add(num_precompile_signatures);
+
+    let micro_lamports = u128::from(compute_budget_limits.compute_unit_price)
+        * u128::from(compute_budget_limits.compute_unit_limit);
+    let prioritization_fee = micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+
+    TransactionFee {
+        base_fee: num_signatures.saturating_mul(LAMPORTS_PER_SIGNATURE),
+        prioritization_fee: u64::try_from(prioritization_fee).unwrap_or(u64::MAX),
+    }
+}
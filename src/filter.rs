@@ -0,0 +1,104 @@
+use {
+    crate::decode::{decode_packet, resolve_transaction, DecodeFailure},
+    solana_address::Address,
+    solana_alt_store::Store,
+    solana_perf::packet::PacketRef,
+    solana_transaction::{
+        sanitized::SanitizedTransaction, versioned::sanitized::SanitizedVersionedTransaction,
+    },
+    std::{collections::HashSet, net::IpAddr},
+};
+
+/// Selects packets by sender IP and by the accounts their transaction uses.
+pub struct PacketFilter {
+    accounts: Option<HashSet<Address>>,
+    ips: Option<HashSet<IpAddr>>,
+    alt_store: Option<Store>,
+}
+
+/// A transaction that passed the filter.
+pub enum FilteredTransaction {
+    /// ALT resolution was skipped, so only static account keys were checked.
+    Unresolved(SanitizedVersionedTransaction),
+    Resolved(SanitizedTransaction),
+}
+
+impl PacketFilter {
+    pub fn new(
+        accounts: Option<HashSet<Address>>,
+        ips: Option<HashSet<IpAddr>>,
+        skip_alt_resolution: bool,
+    ) -> Self {
+        const ALT_STORE_PATH: &str = "alt-store.bin";
+        Self {
+            accounts,
+            ips,
+            alt_store: (!skip_alt_resolution)
+                .then(|| Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store")),
+        }
+    }
+
+    /// Whether the filter has no account or IP restrictions. Packets can still
+    /// be rejected for failing to decode.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_none() && self.ips.is_none()
+    }
+
+    /// Whether `packet` passes the filter. Its transaction is only decoded when
+    /// filtering by accounts, so packets that fail to decode are kept
+    /// otherwise.
+    pub fn keeps(&self, packet: PacketRef) -> bool {
+        if let Some(ips) = &self.ips {
+            if !ips.contains(&packet.meta().addr) {
+                return false;
+            }
+        }
+        self.accounts.is_none() || matches!(self.filter(packet), Ok(Some(_)))
+    }
+
+    /// Decode the transaction in `packet`, and return it if the packet passes
+    /// the filter. Packets from other IPs are rejected without decoding.
+    pub fn filter(&self, packet: PacketRef) -> Result<Option<FilteredTransaction>, DecodeFailure> {
+        if let Some(ips) = &self.ips {
+            if !ips.contains(&packet.meta().addr) {
+                return Ok(None);
+            }
+        }
+        let sanitized_versioned_transaction = decode_packet(packet)?;
+
+        match &self.alt_store {
+            None => {
+                // Skipping ALT resolution, check static SVT account keys.
+                let matches = if let Some(accounts) = &self.accounts {
+                    sanitized_versioned_transaction
+                        .get_message()
+                        .message
+                        .static_account_keys()
+                        .iter()
+                        .any(|account| accounts.contains(account))
+                } else {
+                    true
+                };
+                Ok(matches.then_some(FilteredTransaction::Unresolved(
+                    sanitized_versioned_transaction,
+                )))
+            }
+            Some(alt_store) => {
+                // Resolve ALT. If successful, check all account keys.
+                let sanitized_transaction =
+                    resolve_transaction(sanitized_versioned_transaction, alt_store)?;
+
+                let matches = if let Some(accounts) = &self.accounts {
+                    sanitized_transaction
+                        .message()
+                        .account_keys()
+                        .iter()
+                        .any(|account| accounts.contains(account))
+                } else {
+                    true
+                };
+                Ok(matches.then_some(FilteredTransaction::Resolved(sanitized_transaction)))
+            }
+        }
+    }
+}
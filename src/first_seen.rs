@@ -0,0 +1,164 @@
+use {
+    crate::stats::format_duration_percentiles,
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::process::process_traces,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        collections::HashMap,
+        fs::File,
+        io::{BufWriter, Write},
+        ops::ControlFlow,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    },
+};
+
+/// Report which node saw each non-vote transaction signature first, and how
+/// far ahead of the next node it was. Each trace is given as its event files,
+/// and named by the corresponding entry of `node_names`.
+pub fn first_seen(
+    traces: &[Vec<PathBuf>],
+    node_names: &[String],
+    output: Option<PathBuf>,
+) -> std::io::Result<()> {
+    let mut handler = FirstSeenHandler::new(traces.len());
+    process_traces(traces, &mut |node, event| handler.handle_event(node, event))?;
+    handler.report(node_names, output)
+}
+
+struct FirstSeenHandler {
+    num_nodes: usize,
+    /// First arrival of each signature at each node.
+    arrivals: HashMap<Signature, Vec<Option<SystemTime>>>,
+}
+
+#[derive(Default)]
+struct NodeStatistics {
+    seen: usize,
+    first: usize,
+    /// Lead over the next node to see the signature, for signatures this node
+    /// saw first and at least one other node saw too.
+    leads: Vec<Duration>,
+}
+
+impl FirstSeenHandler {
+    pub fn new(num_nodes: usize) -> Self {
+        Self {
+            num_nodes,
+            arrivals: HashMap::new(),
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        node: usize,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        if let TracedEvent::PacketBatch(label, packet_batches) = event {
+            self.handle_packet_batches(node, timestamp, label, packet_batches);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn handle_packet_batches(
+        &mut self,
+        node: usize,
+        timestamp: SystemTime,
+        label: ChannelLabel,
+        packet_batches: BankingPacketBatch,
+    ) {
+        if !matches!(label, ChannelLabel::NonVote) {
+            return;
+        }
+
+        for packet in packet_batches.iter().flat_map(|b| b.iter()) {
+            let Some(data) = packet.data(..) else {
+                continue;
+            };
+            let Ok(transaction) = bincode::deserialize::<VersionedTransaction>(data) else {
+                continue;
+            };
+            let Some(signature) = transaction.signatures.first() else {
+                continue;
+            };
+
+            let arrivals = self
+                .arrivals
+                .entry(*signature)
+                .or_insert_with(|| vec![None; self.num_nodes]);
+            // Events are merged in timestamp order, so the first arrival at
+            // each node is the one recorded.
+            arrivals[node].get_or_insert(timestamp);
+        }
+    }
+
+    pub fn report(&self, node_names: &[String], output: Option<PathBuf>) -> std::io::Result<()> {
+        let mut writer = output
+            .map(|output| -> std::io::Result<_> {
+                let mut writer = BufWriter::new(File::create(output)?);
+                writeln!(writer, "signature,first_node,num_nodes,lead_us")?;
+                Ok(writer)
+            })
+            .transpose()?;
+
+        let mut node_statistics: Vec<_> = (0..self.num_nodes)
+            .map(|_| NodeStatistics::default())
+            .collect();
+        let mut seen_by_all_count = 0;
+        for (signature, arrivals) in &self.arrivals {
+            let mut node_arrivals: Vec<_> = arrivals
+                .iter()
+                .enumerate()
+                .filter_map(|(node, arrival)| Some((*arrival.as_ref()?, node)))
+                .collect();
+            node_arrivals.sort();
+            for (_, node) in &node_arrivals {
+                node_statistics[*node].seen += 1;
+            }
+            seen_by_all_count += usize::from(node_arrivals.len() == self.num_nodes);
+
+            let (first_arrival, first_node) = node_arrivals[0];
+            let lead = node_arrivals.get(1).map(|(second_arrival, _)| {
+                second_arrival
+                    .duration_since(first_arrival)
+                    .unwrap_or_default()
+            });
+            let statistics = &mut node_statistics[first_node];
+            statistics.first += 1;
+            statistics.leads.extend(lead);
+
+            if let Some(writer) = writer.as_mut() {
+                writeln!(
+                    writer,
+                    "{signature},{},{},{}",
+                    node_names[first_node],
+                    node_arrivals.len(),
+                    lead.map(|lead| lead.as_micros().to_string())
+                        .unwrap_or_default()
+                )?;
+            }
+        }
+        if let Some(mut writer) = writer {
+            writer.flush()?;
+        }
+
+        println!("Unique signatures: {}", self.arrivals.len());
+        println!("Seen by all nodes: {seen_by_all_count}");
+        for (name, statistics) in node_names.iter().zip(node_statistics.iter_mut()) {
+            statistics.leads.sort();
+            println!(
+                "{name}: seen={} first={} first_and_shared={}",
+                statistics.seen,
+                statistics.first,
+                statistics.leads.len(),
+            );
+            println!(
+                "  lead over next node: {}",
+                format_duration_percentiles(&statistics.leads)
+            );
+        }
+        Ok(())
+    }
+}
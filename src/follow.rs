@@ -0,0 +1,286 @@
+use {
+    crate::{process::MAX_RECORD_SIZE, setup::read_timestamp},
+    bincode::Options,
+    solana_core::banking_trace::TimedTracedEvent,
+    std::{
+        collections::VecDeque,
+        fs::File,
+        io::Read,
+        path::{Path, PathBuf},
+        thread,
+        time::{Duration, SystemTime},
+    },
+};
+
+/// How long to wait before checking a followed file for new data again.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most bytes read from the followed file at once, so catching up on a large
+/// file does not read all of it into memory.
+const READ_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Iterator over the events appended to a live trace directory, like
+/// `tail -f`. Reading starts at the beginning of the current `events` file and
+/// never ends, so callers must stop iterating themselves.
+///
+/// Records may be written in pieces, so a record cut off by the end of the
+/// file is kept until the rest of it arrives. When the validator rotates
+/// `events` to `events.1` and starts a new `events`, the rest of the old file
+/// is read through the already open handle before switching to the new file,
+/// so no events are skipped or read twice. If `events` was rotated more than
+/// once since it was last checked, the files in between are read as well.
+///
+/// Files are told apart by the timestamp of their first record, which is
+/// unique in practice and does not depend on platform specific file ids.
+pub struct TraceFollower {
+    directory: PathBuf,
+    path: PathBuf,
+    file: File,
+    /// Timestamp of the first record in `file`, once it has been read.
+    first_timestamp: Option<SystemTime>,
+    /// Files to read once `file` is finished: rotated files that were skipped
+    /// over, oldest first, followed by the new `events` file.
+    next_files: VecDeque<File>,
+    /// Bytes read from `file` that were not decoded yet.
+    buffer: Vec<u8>,
+    /// Offset of the next record in `buffer`.
+    position: usize,
+}
+
+impl TraceFollower {
+    /// Follow the `events` file in the trace directory at `directory`.
+    pub fn open(directory: impl AsRef<Path>) -> std::io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        let path = directory.join("events");
+        let file = File::open(&path)?;
+        Ok(Self {
+            directory,
+            path,
+            file,
+            first_timestamp: None,
+            next_files: VecDeque::new(),
+            buffer: Vec::new(),
+            position: 0,
+        })
+    }
+
+    /// Decode the next complete record in the buffer, if there is one.
+    fn decode_buffered(&mut self) -> std::io::Result<Option<TimedTracedEvent>> {
+        let mut remaining = &self.buffer[self.position..];
+        if remaining.is_empty() {
+            return Ok(None);
+        }
+
+        let options = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(MAX_RECORD_SIZE);
+        match options.deserialize_from::<_, TimedTracedEvent>(&mut remaining) {
+            Ok(event) => {
+                self.position = self.buffer.len() - remaining.len();
+                Ok(Some(event))
+            }
+            Err(err) if is_partial_record(&err) => Ok(None),
+            Err(err) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("undecodable record in {}: {err}", self.path.display()),
+            )),
+        }
+    }
+
+    /// Read the next chunk of data appended to the current file. Returns the
+    /// number of new bytes, which is zero at the end of the file.
+    fn read_appended(&mut self) -> std::io::Result<usize> {
+        self.buffer.drain(..self.position);
+        self.position = 0;
+        let num_bytes = (&mut self.file)
+            .take(READ_CHUNK_SIZE)
+            .read_to_end(&mut self.buffer)?;
+        // The buffer is only drained past records that were decoded, so it
+        // still starts at the beginning of the file until the first timestamp
+        // has been read.
+        if self.first_timestamp.is_none() {
+            self.first_timestamp = read_timestamp(self.buffer.as_slice()).ok();
+        }
+        Ok(num_bytes)
+    }
+
+    /// Switch to the next file if the current one was rotated away. Must only
+    /// be called once the current file has been read to the end. Returns
+    /// whether there is anything new to decode.
+    fn follow_rotation(&mut self) -> std::io::Result<bool> {
+        if self.next_files.is_empty() {
+            let first_timestamp = match read_first_timestamp(&self.path) {
+                Ok(first_timestamp) => first_timestamp,
+                // The new file may not have been created yet.
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+                Err(err) => return Err(err),
+            };
+            // A file without a complete first timestamp is only known to be
+            // new if the current file has one.
+            let rotated = match first_timestamp {
+                Some(first_timestamp) => self.first_timestamp != Some(first_timestamp),
+                None => self.first_timestamp.is_some(),
+            };
+            if !rotated {
+                return Ok(false);
+            }
+            // Events may have been appended between reading the old file to
+            // the end and the rotation, so read it once more before switching.
+            if self.read_appended()? > 0 {
+                return Ok(true);
+            }
+
+            self.next_files = self.skipped_rotated_files()?;
+            self.next_files.push_back(File::open(&self.path)?);
+        }
+
+        // Nothing more is written to a rotated file, so the final record of
+        // the old file must have been cut off.
+        let trailing_bytes = self.buffer.len() - self.position;
+        if trailing_bytes > 0 {
+            eprintln!(
+                "Warning: {trailing_bytes} trailing bytes ignored in a rotated event file in {}",
+                self.directory.display()
+            );
+        }
+
+        if let Some(file) = self.next_files.pop_front() {
+            self.file = file;
+        }
+        self.first_timestamp = None;
+        self.buffer.clear();
+        self.position = 0;
+        Ok(true)
+    }
+
+    /// Open the files rotated in after the current file, oldest first. The
+    /// current file is found by its first timestamp among `events.1`,
+    /// `events.2`, and so on. Each rotation in between left a file there.
+    fn skipped_rotated_files(&self) -> std::io::Result<VecDeque<File>> {
+        let mut skipped_paths = Vec::new();
+        for index in 1.. {
+            let path = self.directory.join(format!("events.{index}"));
+            match read_first_timestamp(&path) {
+                Ok(first_timestamp) if first_timestamp == self.first_timestamp => {
+                    return skipped_paths.into_iter().rev().map(File::open).collect();
+                }
+                Ok(_) => skipped_paths.push(path),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        eprintln!(
+            "Warning: the followed event file in {} was rotated away or removed. Events in the \
+             files rotated since may have been skipped.",
+            self.directory.display()
+        );
+        Ok(VecDeque::new())
+    }
+}
+
+impl Iterator for TraceFollower {
+    type Item = std::io::Result<TimedTracedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.decode_buffered() {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => {}
+                Err(err) => return Some(Err(err)),
+            }
+
+            match self.read_appended() {
+                Ok(0) => {}
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+
+            match self.follow_rotation() {
+                Ok(true) => continue,
+                Ok(false) => thread::sleep(POLL_INTERVAL),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+fn is_partial_record(err: &bincode::Error) -> bool {
+    matches!(
+        &**err,
+        bincode::ErrorKind::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Timestamp of the first record in the event file at `path`, or `None` if it
+/// is not complete yet.
+fn read_first_timestamp(path: &Path) -> std::io::Result<Option<SystemTime>> {
+    match read_timestamp(File::open(path)?) {
+        Ok(first_timestamp) => Ok(Some(first_timestamp)),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_core::banking_trace::TracedEvent, solana_hash::Hash, std::time::UNIX_EPOCH,
+    };
+
+    /// Write an event file holding a `BlockAndBankHash` for `slot`, at `slot`
+    /// seconds.
+    fn write_event_file(path: &Path, slot: u64) {
+        let event = TimedTracedEvent(
+            UNIX_EPOCH + Duration::from_secs(slot),
+            TracedEvent::BlockAndBankHash(slot, Hash::default(), Hash::default()),
+        );
+        std::fs::write(path, bincode::serialize(&event).unwrap()).unwrap();
+    }
+
+    fn next_slot(follower: &mut TraceFollower) -> u64 {
+        match follower.next().unwrap().unwrap() {
+            TimedTracedEvent(_, TracedEvent::BlockAndBankHash(slot, _, _)) => slot,
+            TimedTracedEvent(_, TracedEvent::PacketBatch(_, _)) => panic!("unexpected packets"),
+        }
+    }
+
+    #[test]
+    fn test_follow_starts_on_empty_file() {
+        let directory =
+            std::env::temp_dir().join(format!("follow-empty-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("events");
+
+        std::fs::write(&path, []).unwrap();
+        let mut follower = TraceFollower::open(&directory).unwrap();
+        write_event_file(&path, 1);
+        assert_eq!(next_slot(&mut follower), 1);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_follow_reads_files_skipped_by_rotation() {
+        let directory = std::env::temp_dir().join(format!("follow-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = |name: &str| directory.join(name);
+
+        write_event_file(&path("events"), 1);
+        let mut follower = TraceFollower::open(&directory).unwrap();
+        assert_eq!(next_slot(&mut follower), 1);
+
+        // Rotate twice before the follower checks again.
+        std::fs::rename(path("events"), path("events.1")).unwrap();
+        write_event_file(&path("events"), 2);
+        std::fs::rename(path("events.1"), path("events.2")).unwrap();
+        std::fs::rename(path("events"), path("events.1")).unwrap();
+        write_event_file(&path("events"), 3);
+
+        assert_eq!(next_slot(&mut follower), 2);
+        assert_eq!(next_slot(&mut follower), 3);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
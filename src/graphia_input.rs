@@ -1,19 +1,12 @@
 use {
-    crate::process::process_event_files,
-    agave_banking_stage_ingress_types::BankingPacketBatch,
+    crate::{
+        fee::transaction_fee,
+        slot_packets::{read_slot_packets, SlotTransactions},
+    },
     prio_graph::{AccessKind, PrioGraph, TopLevelId},
     serde::Serialize,
-    solana_alt_store::Store,
-    solana_borsh::v1::try_from_slice_unchecked,
     solana_clock::Slot,
-    solana_compute_budget_interface::ComputeBudgetInstruction,
-    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
-    solana_sdk_ids::compute_budget,
-    solana_transaction::{
-        sanitized::SanitizedTransaction,
-        versioned::{sanitized::SanitizedVersionedTransaction, VersionedTransaction},
-    },
-    std::{collections::HashSet, path::PathBuf},
+    std::path::PathBuf,
 };
 
 pub fn graphia_input(
@@ -21,183 +14,141 @@ pub fn graphia_input(
     slot: Slot,
     output: PathBuf,
 ) -> std::io::Result<()> {
-    let mut handler = GraphiaInputHandler::new(slot);
-    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
-    handler.report(output)
-}
-
-struct GraphiaInputHandler {
-    slot: Slot,
-    current_packet_batches: Vec<BankingPacketBatch>,
-    done: bool,
-    alt_store: Store,
+    let slot_packets = read_slot_packets(event_file_paths, slot)?;
+    report(slot_packets.transactions(), output)
 }
 
-impl GraphiaInputHandler {
-    pub fn new(slot: Slot) -> Self {
-        const ALT_STORE_PATH: &str = "alt-store.bin";
-
-        Self {
-            slot,
-            current_packet_batches: Vec::new(),
-            done: false,
-            alt_store: Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store"),
-        }
-    }
-
-    pub fn handle_event(&mut self, TimedTracedEvent(_timestamp, event): TimedTracedEvent) {
-        if self.done {
-            return;
-        }
-
-        match event {
-            TracedEvent::PacketBatch(label, packet_batches) => {
-                self.handle_packet_batches(label, packet_batches)
-            }
-            TracedEvent::BlockAndBankHash(slot, _, _) => self.handle_block_and_bank_hash(slot),
-        }
-    }
+/// Write JSON for prio-graph of the slot.
+/// Each transaction has following attributes:
+/// - Signature
+/// - Priority
+/// - Requested CUs
+/// - Fee
+fn report(slot_transactions: SlotTransactions, output: PathBuf) -> std::io::Result<()> {
+    slot_transactions.report_decode_failures();
+
+    // Buffer all (transaction, priority, requested_cus, fee) tuples.
+    let mut transaction_tuples: Vec<_> = slot_transactions
+        .transactions
+        .into_iter()
+        .map(|(tx, compute_budget_limits)| {
+            let fee = transaction_fee(
+                tx.signatures().len(),
+                tx.message().program_instructions_iter(),
+                &compute_budget_limits,
+            );
+            (
+                tx,
+                compute_budget_limits.compute_unit_price,
+                u64::from(compute_budget_limits.compute_unit_limit),
+                fee.total(),
+            )
+        })
+        .collect();
+
+    // Sort by priority. Highest priority first.
+    transaction_tuples.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut graphia_input = GraphiaInput::default();
+
+    // Insert into prio-graph in order of priority.
+    let mut prio_graph = PrioGraph::new(|pi, _| *pi);
+    let mut transaction_iterator = transaction_tuples.iter().enumerate();
+    let mut insert_next_transaction = |prio_graph: &mut PrioGraph<_, _, _, _>| {
+        let Some((index, (transaction, priority, _, _))) = transaction_iterator.next() else {
+            return false;
+        };
 
-    /// Write JSON for prio-graph of the current slot.
-    /// Each transaction has following attributes:
-    /// - Signature
-    /// - Priority
-    /// - Requested CUs
-    pub fn report(&self, output: PathBuf) -> std::io::Result<()> {
-        // Buffer all (transaction, priority, requested_cus) tuples.
-        let mut transaction_tuples: Vec<_> = self
-            .current_packet_batches
+        let account_locks = transaction.get_account_locks_unchecked();
+        let write_locks = account_locks
+            .writable
             .iter()
-            .flat_map(|b| b.iter().flat_map(|b| b.iter()))
-            .filter_map(|p| bincode::deserialize::<VersionedTransaction>(p.data(..)?).ok())
-            .filter_map(|tx| SanitizedVersionedTransaction::try_from(tx).ok())
-            .map(|tx| {
-                let (priority, requested_cus) = get_priority_and_requested_cus(&tx);
-                (tx, priority, requested_cus)
-            })
-            .filter_map(|(tx, priority, requested_cus)| {
-                let hash = tx.get_message().message.hash();
-                SanitizedTransaction::try_new(tx, hash, false, &self.alt_store, &HashSet::new())
-                    .ok()
-                    .map(|tx| (tx, priority, requested_cus))
-            })
-            .collect();
-
-        // Sort by priority. Highest priority first.
-        transaction_tuples.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // Insert into prio-graph in order of priority.
-        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-        struct PriorityIndex {
-            priority: u64,
-            index: usize,
-        }
-        impl TopLevelId<PriorityIndex> for PriorityIndex {
-            fn id(&self) -> PriorityIndex {
-                *self
-            }
-        }
-        impl Ord for PriorityIndex {
-            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                self.priority.cmp(&other.priority)
-            }
-        }
-        impl PartialOrd for PriorityIndex {
-            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                Some(self.cmp(other))
-            }
-        }
-
-        let mut graphia_input = GraphiaInput::default();
-        let mut prio_graph = PrioGraph::new(|pi, _| *pi);
-        let mut transaction_iterator = transaction_tuples.iter().enumerate();
-        let mut insert_next_transaction = |prio_graph: &mut PrioGraph<_, _, _, _>| {
-            let Some((index, (transaction, priority, _))) = transaction_iterator.next() else {
-                return false;
-            };
-
-            let account_locks = transaction.get_account_locks_unchecked();
-            let write_locks = account_locks
-                .writable
-                .iter()
-                .map(|a| (*a, AccessKind::Write));
-            let read_locks = account_locks
-                .readonly
-                .iter()
-                .map(|a| (*a, AccessKind::Read));
-            let transaction_access = write_locks.chain(read_locks);
-
-            prio_graph.insert_transaction(
-                PriorityIndex {
+            .map(|a| (*a, AccessKind::Write));
+        let read_locks = account_locks
+            .readonly
+            .iter()
+            .map(|a| (*a, AccessKind::Read));
+        let transaction_access = write_locks.chain(read_locks);
+
+        prio_graph.insert_transaction(
+            PriorityIndex {
+                priority: *priority,
+                index,
+            },
+            transaction_access,
+        );
+
+        true
+    };
+
+    while insert_next_transaction(&mut prio_graph) {}
+
+    let mut edge_count = 0;
+    while !prio_graph.is_empty() {
+        let mut popped = Vec::new();
+        while let Some(id) = prio_graph.pop() {
+            popped.push(id);
+
+            // Insert a new node into the graphia input graph.
+            let (tx, priority, requested_cus, fee) = &transaction_tuples[id.index];
+            graphia_input.graph.nodes.push(GraphiaInputNode {
+                id: id.index.to_string(),
+                metadata: GraphiaInputNodeMetaData {
+                    signature: tx.signature().to_string(),
                     priority: *priority,
-                    index,
+                    requested_cus: *requested_cus,
+                    fee: *fee,
                 },
-                transaction_access,
-            );
-
-            true
-        };
-
-        while insert_next_transaction(&mut prio_graph) {}
-
-        let mut edge_count = 0;
-        while !prio_graph.is_empty() {
-            let mut popped = Vec::new();
-            while let Some(id) = prio_graph.pop() {
-                popped.push(id);
-
-                // Insert a new node into the graphia input graph.
-                let (tx, priority, requested_cus) = &transaction_tuples[id.index];
-                graphia_input.graph.nodes.push(GraphiaInputNode {
-                    id: id.index.to_string(),
-                    metadata: GraphiaInputNodeMetaData {
-                        signature: tx.signature().to_string(),
-                        priority: *priority,
-                        requested_cus: *requested_cus,
-                    },
-                });
-            }
-
-            for popped in popped {
-                let unblocked = prio_graph.unblock(&popped);
+            });
+        }
 
-                // Add edges to graphia input graph.
-                for target in unblocked {
-                    if !prio_graph.is_blocked(target) {
-                        graphia_input.graph.edges.push(GraphiaInputEdge {
-                            id: edge_count.to_string(),
-                            // metadata: GraphiaInputEdgeMetaData {},
-                            source: popped.index.to_string(),
-                            target: target.index.to_string(),
-                        });
-                        edge_count += 1;
-                    }
+        for popped in popped {
+            let unblocked = prio_graph.unblock(&popped);
+
+            // Add edges to graphia input graph.
+            for target in unblocked {
+                if !prio_graph.is_blocked(target) {
+                    graphia_input.graph.edges.push(GraphiaInputEdge {
+                        id: edge_count.to_string(),
+                        // metadata: GraphiaInputEdgeMetaData {},
+                        source: popped.index.to_string(),
+                        target: target.index.to_string(),
+                    });
+                    edge_count += 1;
                 }
             }
         }
-
-        let file = std::fs::File::options()
-            .write(true)
-            .create(true)
-            .append(false)
-            .truncate(true)
-            .open(output)?;
-        serde_json::to_writer(file, &graphia_input).unwrap();
-        Ok(())
     }
 
-    fn handle_packet_batches(&mut self, label: ChannelLabel, packet_batches: BankingPacketBatch) {
-        if matches!(label, ChannelLabel::NonVote) {
-            self.current_packet_batches.push(packet_batches);
-        }
-    }
+    let file = std::fs::File::options()
+        .write(true)
+        .create(true)
+        .append(false)
+        .truncate(true)
+        .open(output)?;
+    serde_json::to_writer(file, &graphia_input).unwrap();
+    Ok(())
+}
 
-    fn handle_block_and_bank_hash(&mut self, slot: Slot) {
-        if self.slot != slot {
-            self.current_packet_batches.clear();
-        } else {
-            self.done = true;
-        }
+/// Id of a transaction in a `PrioGraph`, ordered by priority.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PriorityIndex {
+    pub priority: u64,
+    pub index: usize,
+}
+impl TopLevelId<PriorityIndex> for PriorityIndex {
+    fn id(&self) -> PriorityIndex {
+        *self
+    }
+}
+impl Ord for PriorityIndex {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.priority.cmp(&other.priority)
+    }
+}
+impl PartialOrd for PriorityIndex {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -245,36 +196,5 @@ struct GraphiaInputNodeMetaData {
     signature: String,
     priority: u64,
     requested_cus: u64,
-}
-
-/// Returns priorty and requested_cus
-fn get_priority_and_requested_cus(tx: &SanitizedVersionedTransaction) -> (u64, u64) {
-    let instructions = tx.get_message().program_instructions_iter();
-    let mut non_compute_budget_ix_count = 0u64;
-    let mut priority = 0u64;
-    let mut requested_cus = None;
-    for (program, ix) in instructions {
-        if !compute_budget::check_id(program) {
-            non_compute_budget_ix_count += 1;
-            continue;
-        }
-
-        let ix: ComputeBudgetInstruction = try_from_slice_unchecked(&ix.data).unwrap();
-        match ix {
-            ComputeBudgetInstruction::RequestHeapFrame(_) => {}
-            ComputeBudgetInstruction::SetComputeUnitLimit(units) => {
-                requested_cus = Some(units as u64)
-            }
-            ComputeBudgetInstruction::SetComputeUnitPrice(cu_price) => priority = cu_price,
-            ComputeBudgetInstruction::Unused
-            | ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(_) => {}
-        }
-    }
-
-    (
-        priority,
-        requested_cus
-            .unwrap_or(non_compute_budget_ix_count * 200_000)
-            .max(1_400_000),
-    )
+    fee: u64,
 }
@@ -0,0 +1,33 @@
+use {banking_trace_tool::seek_index::SeekIndex, std::path::PathBuf};
+
+/// Write an index for each trace. Each trace is given as its event files.
+pub fn index(traces: &[Vec<PathBuf>]) -> std::io::Result<()> {
+    for event_file_paths in traces {
+        index_trace(event_file_paths)?;
+    }
+    Ok(())
+}
+
+fn index_trace(event_file_paths: &[PathBuf]) -> std::io::Result<()> {
+    let Some(index_path) = SeekIndex::path(event_file_paths) else {
+        return Err(std::io::Error::other("no event files to index"));
+    };
+
+    let index = SeekIndex::build(event_file_paths)?;
+    index.write(&index_path)?;
+
+    for file in &index.files {
+        let num_slots = file
+            .checkpoints
+            .iter()
+            .filter(|checkpoint| checkpoint.slot.is_some())
+            .count();
+        println!(
+            "{}: {} checkpoints, {num_slots} slots",
+            file.file_name.display(),
+            file.checkpoints.len()
+        );
+    }
+    println!("Wrote index to {}", index_path.display());
+    Ok(())
+}
@@ -0,0 +1,14 @@
+//! Reading and writing of Solana banking-trace event files.
+//!
+//! [`TraceReader`] iterates over the events of a trace directory in timestamp
+//! order, and [`setup::get_event_file_paths`] finds the event files in one.
+//! [`TraceFollower`] tails a live trace directory as the validator writes it,
+//! and [`TraceWriter`] writes events back out as a trace directory.
+
+pub mod follow;
+pub mod process;
+pub mod seek_index;
+pub mod setup;
+pub mod trace_writer;
+
+pub use {follow::TraceFollower, process::TraceReader, trace_writer::TraceWriter};
@@ -1,70 +1,235 @@
 use {
     crate::{
-        account_usage::account_usage, cli::Cli, graphia_input::graphia_input,
-        packet_count::packet_count, slot_ranges::slot_ranges, time_range::time_range,
+        account_usage::account_usage,
+        bank_hashes::bank_hashes,
+        blockhash_age::blockhash_age,
+        cli::Cli,
+        first_seen::first_seen,
+        graphia_input::graphia_input,
+        index::index,
+        packet_count::{packet_count, TimeSeriesConfig},
+        priority_fees::priority_fees,
+        program_usage::program_usage,
+        redact::redact,
+        resends::resends,
+        simulate_block::{simulate_block, BlockLimits},
+        simulate_scheduler::simulate_scheduler,
+        slice::slice,
+        slot_ranges::slot_ranges,
+        slot_summary::slot_summary,
+        slot_timing::slot_timing,
+        time_range::time_range,
         update_alt_store::update_alt_store,
+        verify::verify,
+        vote_stats::vote_stats,
     },
+    banking_trace_tool::setup::get_event_file_paths,
     chrono::{DateTime, Utc},
     clap::Parser,
     cli::TraceToolMode,
-    setup::get_event_file_paths,
-    std::process::exit,
+    solana_clock::NUM_CONSECUTIVE_LEADER_SLOTS,
+    std::{path::PathBuf, process::exit, time::Duration},
 };
 
 mod account_usage;
+mod bank_hashes;
+mod blockhash_age;
 mod cli;
+mod compute_budget;
+mod cost_model;
+mod decode;
 mod dump;
+mod fee;
+mod filter;
+mod first_seen;
 mod graphia_input;
+mod index;
 mod packet_count;
-mod process;
-mod setup;
+mod priority_fees;
+mod program_usage;
+mod redact;
+mod resends;
+mod simulate_block;
+mod simulate_scheduler;
+mod slice;
+mod slot_packets;
 mod slot_ranges;
+mod slot_summary;
+mod slot_timing;
+mod stats;
 mod time_range;
 mod update_alt_store;
+mod verify;
+mod vote_stats;
 
 fn main() {
     let Cli { path, mode } = Cli::parse();
 
-    if !path.is_dir() {
-        eprintln!("{} is not a directory", path.display());
+    // Other commands read a single trace, and would count the events of
+    // several traces over again.
+    let is_multi_trace = matches!(
+        mode,
+        TraceToolMode::BankHashes { .. } | TraceToolMode::FirstSeen { .. } | TraceToolMode::Index
+    );
+    if path.len() > 1 && !is_multi_trace {
+        eprintln!("Only bank-hashes, first-seen and index accept more than one --path");
         exit(1);
     }
 
-    let event_file_paths = get_event_file_paths(path);
+    // A followed directory is read by `TraceFollower` as it is written, and
+    // its event files may not have a first timestamp yet.
+    let follow = matches!(
+        mode,
+        TraceToolMode::Dump { follow: true, .. } | TraceToolMode::PacketCount { follow: true, .. }
+    );
+    let traces: Vec<_> = if follow {
+        Vec::new()
+    } else {
+        path.iter()
+            .map(|path| {
+                if !path.exists() {
+                    eprintln!("{} does not exist", path.display());
+                    exit(1);
+                }
+                get_event_file_paths(path).unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    exit(1);
+                })
+            })
+            .collect()
+    };
+    let event_file_paths = traces.concat();
+    let trace_names: Vec<_> = path.iter().map(|path| path.display().to_string()).collect();
     let result = match mode {
         TraceToolMode::AccountUsage(slot_range) => account_usage(&event_file_paths, slot_range),
+        TraceToolMode::BankHashes { reference, output } => {
+            bank_hashes(&traces, &trace_names, reference, output)
+        }
+        TraceToolMode::BlockhashAge {
+            ip_count,
+            account_count,
+        } => blockhash_age(&event_file_paths, ip_count, account_count),
         TraceToolMode::Dump {
             accounts,
             ips,
             skip_alt_resolution,
             start_timestamp,
             end_timestamp,
+            follow,
         } => dump::dump(
             &event_file_paths,
+            follow.then(|| follow_directory(&path)),
             accounts.map(|accounts| accounts.into_iter().collect()),
             ips.map(|ips| ips.into_iter().collect()),
             skip_alt_resolution,
             start_timestamp.map(cli_parse_timestamp),
             end_timestamp.map(cli_parse_timestamp),
         ),
+        TraceToolMode::FirstSeen { output } => first_seen(&traces, &trace_names, output),
         TraceToolMode::GraphiaInput { slot, output } => {
             graphia_input(&event_file_paths, slot, output)
         }
+        TraceToolMode::Index => index(&traces),
         TraceToolMode::PacketCount {
             start_timestamp,
             end_timestamp,
             ip_count,
+            follow,
+            report_slots,
+            time_series,
+            bucket_ms,
+            time_series_format,
         } => packet_count(
             &event_file_paths,
+            follow.then(|| follow_directory(&path)),
             start_timestamp.map(cli_parse_timestamp),
             end_timestamp.map(cli_parse_timestamp),
             ip_count.unwrap_or(5),
+            report_slots.or(follow.then_some(NUM_CONSECUTIVE_LEADER_SLOTS)),
+            time_series.map(|output| TimeSeriesConfig {
+                output,
+                bucket_width: Duration::from_millis(bucket_ms),
+                format: time_series_format,
+            }),
+        ),
+        TraceToolMode::PriorityFees {
+            slot_range,
+            output,
+            percentiles_output,
+            account_count,
+        } => priority_fees(
+            &event_file_paths,
+            slot_range,
+            output,
+            percentiles_output,
+            account_count,
+        ),
+        TraceToolMode::ProgramUsage(slot_range) => program_usage(&event_file_paths, slot_range),
+        TraceToolMode::Redact {
+            output,
+            key_file,
+            zero_port,
+            strip_signers,
+            max_file_size,
+        } => redact(
+            &event_file_paths,
+            output,
+            key_file,
+            zero_port,
+            strip_signers.map(|signers| signers.into_iter().collect()),
+            max_file_size,
+        ),
+        TraceToolMode::Resends { output, ip_count } => resends(&event_file_paths, output, ip_count),
+        TraceToolMode::SimulateBlock {
+            slot,
+            block_cu_limit,
+            account_cu_limit,
+            vote_reservation,
+            skipped_count,
+        } => simulate_block(
+            &event_file_paths,
+            slot,
+            BlockLimits {
+                block_cu_limit,
+                account_cu_limit,
+                vote_reservation,
+            },
+            skipped_count,
+        ),
+        TraceToolMode::SimulateScheduler {
+            slot,
+            num_threads,
+            batch_size,
+        } => simulate_scheduler(&event_file_paths, slot, num_threads, batch_size),
+        TraceToolMode::Slice {
+            output,
+            start_slot,
+            end_slot,
+            accounts,
+            ips,
+            skip_alt_resolution,
+            max_file_size,
+        } => slice(
+            &event_file_paths,
+            output,
+            start_slot,
+            end_slot,
+            accounts.map(|accounts| accounts.into_iter().collect()),
+            ips.map(|ips| ips.into_iter().collect()),
+            skip_alt_resolution,
+            max_file_size,
         ),
         TraceToolMode::SlotRanges => slot_ranges(&event_file_paths),
+        TraceToolMode::SlotSummary { csv } => slot_summary(&event_file_paths, csv),
+        TraceToolMode::SlotTiming { target_ms } => {
+            slot_timing(&event_file_paths, Duration::from_millis(target_ms))
+        }
         TraceToolMode::TimeRange => time_range(&event_file_paths),
         TraceToolMode::UpdateAltStore(slot_range) => {
             update_alt_store(&event_file_paths, slot_range)
         }
+        TraceToolMode::Verify { keep_going } => verify(&event_file_paths, keep_going),
+        TraceToolMode::VoteStats { account_count } => vote_stats(&event_file_paths, account_count),
     };
 
     if let Err(err) = result {
@@ -73,6 +238,17 @@ fn main() {
     }
 }
 
+/// The trace directory to follow. Following only supports a single directory.
+fn follow_directory(paths: &[PathBuf]) -> PathBuf {
+    match paths {
+        [path] if path.is_dir() => path.clone(),
+        _ => {
+            eprintln!("--follow requires a single trace directory");
+            exit(1);
+        }
+    }
+}
+
 fn cli_parse_timestamp(s: String) -> DateTime<Utc> {
     s.parse().expect("Failed to parse timestamp")
 }
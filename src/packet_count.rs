@@ -1,35 +1,75 @@
 use {
-    crate::process::process_event_files,
+    crate::{
+        cli::TimeSeriesFormat,
+        decode::{deserialize_packet, DecodeFailure, DecodeFailureCounts},
+    },
     agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{
+        process::{follow_event_files, process_event_files, process_event_files_from},
+        seek_index::SeekTarget,
+    },
     chrono::{DateTime, Utc},
+    serde::Serialize,
     solana_clock::Slot,
     solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_perf::packet::PacketRef,
     solana_signature::Signature,
-    solana_transaction::versioned::VersionedTransaction,
+    solana_transaction::versioned::sanitized::SanitizedVersionedTransaction,
     std::{
         collections::{HashMap, HashSet},
+        fs::File,
+        io::{BufWriter, Write},
         net::IpAddr,
+        ops::ControlFlow,
         path::PathBuf,
+        time::{Duration, SystemTime, UNIX_EPOCH},
     },
 };
 
+/// Count packets in the trace, or in the live trace directory `follow` as it is
+/// written. If `report_slots` is set, a report is printed and the counts are
+/// reset every `report_slots` slots. If `time_series` is set, the counts are
+/// also written per time bucket.
 pub fn packet_count(
     event_file_paths: &[PathBuf],
+    follow: Option<PathBuf>,
     start_timestamp: Option<DateTime<Utc>>,
     end_timestamp: Option<DateTime<Utc>>,
     ip_count: usize,
+    report_slots: Option<u64>,
+    time_series: Option<TimeSeriesConfig>,
 ) -> std::io::Result<()> {
-    let mut handler = PacketCounter::new(start_timestamp, end_timestamp);
-    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
-    handler.report(ip_count);
-    Ok(())
+    let time_series = time_series.map(PacketTimeSeries::create).transpose()?;
+    let mut handler = PacketCounter::new(
+        start_timestamp,
+        end_timestamp,
+        ip_count,
+        report_slots,
+        time_series,
+    );
+    let mut handler_fn = |event| handler.handle_event(event);
+    match (follow, start_timestamp) {
+        (Some(directory), _) => follow_event_files(directory, &mut handler_fn)?,
+        (None, Some(start_timestamp)) => process_event_files_from(
+            event_file_paths,
+            SeekTarget::Timestamp(start_timestamp.into()),
+            &mut handler_fn,
+        )?,
+        (None, None) => process_event_files(event_file_paths, &mut handler_fn)?,
+    }
+    handler.finish()
 }
 
 struct PacketCounter {
     start_timestamp: Option<DateTime<Utc>>,
     end_timestamp: Option<DateTime<Utc>>,
     started: bool,
-    done: bool,
+    num_ips_to_report: usize,
+    report_slots: Option<u64>,
+    slots_since_report: u64,
+    time_series: Option<PacketTimeSeries>,
+    /// First time series write error, which stops processing.
+    error: Option<std::io::Error>,
 
     packet_metrics: PacketMetrics,
 }
@@ -58,6 +98,7 @@ struct PacketMetrics {
     fwd_ip_counts: HashMap<IpAddr, IpPacketCounts>,
 
     signature_set: HashSet<Signature>,
+    decode_failures: DecodeFailureCounts,
 }
 
 #[derive(Default)]
@@ -68,22 +109,265 @@ struct IpPacketCounts {
     staked: usize,
 }
 
+/// Properties of a packet recorded in `PacketMetrics`.
+#[derive(Clone, Copy)]
+struct PacketProperties {
+    valid: bool,
+    staked: bool,
+    forwarded: bool,
+    /// The first signature of the packet's transaction, if it decoded.
+    signature: Option<Signature>,
+}
+
+impl PacketMetrics {
+    fn record_packet(&mut self, packet: PacketRef) -> PacketProperties {
+        // Ignore any packet that was filtered by sigverify
+        self.total_count += 1;
+
+        let valid = !packet.meta().discard();
+        let staked = packet.meta().is_from_staked_node();
+        let forwarded = packet.meta().forwarded();
+
+        // Signatures are counted whether or not the transaction sanitizes.
+        // Sanitize failures are counted with the other decode failures.
+        let signature = match deserialize_packet(packet) {
+            Ok(transaction) => {
+                let signature = transaction.signatures.first().copied();
+                if let Err(err) = SanitizedVersionedTransaction::try_from(transaction) {
+                    self.decode_failures
+                        .record(packet.meta().addr, DecodeFailure::from(err));
+                }
+                signature
+            }
+            Err(failure) => {
+                self.decode_failures.record(packet.meta().addr, failure);
+                None
+            }
+        };
+        let unique = signature.is_some_and(|signature| self.signature_set.insert(signature));
+
+        self.valid_count += usize::from(valid);
+        self.valid_unique_count += usize::from(valid && unique);
+
+        self.tpu_count += usize::from(valid && !forwarded);
+        self.fwd_count += usize::from(valid && forwarded);
+
+        self.staked_count += usize::from(valid && staked);
+        self.staked_tpu_count += usize::from(valid && staked && !forwarded);
+        self.staked_fwd_count += usize::from(valid && staked && forwarded);
+
+        self.tpu_unique_count += usize::from(valid && !forwarded && unique);
+        self.fwd_unique_count += usize::from(valid && forwarded && unique);
+
+        self.tpu_staked_unique_count += usize::from(valid && !forwarded && staked && unique);
+        self.fwd_staked_unique_count += usize::from(valid && forwarded && staked && unique);
+
+        let update_ip_counts = |ip_counts: &mut HashMap<IpAddr, IpPacketCounts>,
+                                ip: IpAddr,
+                                unique: bool,
+                                staked: bool| {
+            let ip_packet_counts = ip_counts.entry(ip).or_default();
+            ip_packet_counts.total += 1;
+            ip_packet_counts.valid += usize::from(valid);
+            ip_packet_counts.unique += usize::from(valid && unique);
+            ip_packet_counts.staked += usize::from(valid && staked);
+        };
+
+        update_ip_counts(
+            &mut self.total_ip_counts,
+            packet.meta().addr,
+            unique,
+            staked,
+        );
+        if !forwarded {
+            update_ip_counts(&mut self.tpu_ip_counts, packet.meta().addr, unique, staked);
+        } else {
+            update_ip_counts(&mut self.fwd_ip_counts, packet.meta().addr, unique, staked);
+        }
+
+        PacketProperties {
+            valid,
+            staked,
+            forwarded,
+            signature,
+        }
+    }
+}
+
+/// Where and how to write a packet time series.
+pub struct TimeSeriesConfig {
+    pub output: PathBuf,
+    pub bucket_width: Duration,
+    pub format: TimeSeriesFormat,
+}
+
+/// Writes packet counts per time bucket. Buckets are aligned to the Unix
+/// epoch, and buckets without packets are written as zeros, so the series can
+/// be plotted directly. Each bucket is flushed once written, so the series can
+/// be watched while following a live trace.
+struct PacketTimeSeries {
+    writer: BufWriter<File>,
+    bucket_width: Duration,
+    format: TimeSeriesFormat,
+    /// Index of the current bucket since the Unix epoch.
+    current_bucket: Option<u128>,
+    counts: BucketCounts,
+    /// Signatures seen over the whole series. Kept apart from `PacketMetrics`,
+    /// which is reset on every report.
+    signature_set: HashSet<Signature>,
+}
+
+/// Counts of a time bucket, matching the counts in `PacketMetrics`.
+#[derive(Default, Serialize)]
+struct BucketCounts {
+    total: usize,
+    valid: usize,
+    staked: usize,
+    forwarded: usize,
+    /// Valid packets whose signature was seen for the first time.
+    unique: usize,
+}
+
+#[derive(Serialize)]
+struct TimeSeriesRow<'a> {
+    bucket_start: String,
+    #[serde(flatten)]
+    counts: &'a BucketCounts,
+}
+
+impl PacketTimeSeries {
+    fn create(config: TimeSeriesConfig) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(config.output)?);
+        if matches!(config.format, TimeSeriesFormat::Csv) {
+            writeln!(writer, "bucket_start,total,valid,staked,forwarded,unique")?;
+        }
+        Ok(Self {
+            writer,
+            bucket_width: config.bucket_width,
+            format: config.format,
+            current_bucket: None,
+            counts: BucketCounts::default(),
+            signature_set: HashSet::new(),
+        })
+    }
+
+    fn record_packet(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        properties: PacketProperties,
+    ) -> std::io::Result<()> {
+        let bucket = SystemTime::from(timestamp)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            / self.bucket_width.as_nanos().max(1);
+        match self.current_bucket {
+            None => self.current_bucket = Some(bucket),
+            Some(current_bucket) if bucket > current_bucket => {
+                self.write_bucket()?;
+                for empty_bucket in current_bucket + 1..bucket {
+                    self.current_bucket = Some(empty_bucket);
+                    self.write_bucket()?;
+                }
+                self.writer.flush()?;
+                self.current_bucket = Some(bucket);
+            }
+            // Timestamps are only roughly ordered, so late packets are counted
+            // in the current bucket.
+            Some(_) => {}
+        }
+
+        let PacketProperties {
+            valid,
+            staked,
+            forwarded,
+            signature,
+        } = properties;
+        let unique = signature.is_some_and(|signature| self.signature_set.insert(signature));
+        self.counts.total += 1;
+        self.counts.valid += usize::from(valid);
+        self.counts.staked += usize::from(valid && staked);
+        self.counts.forwarded += usize::from(valid && forwarded);
+        self.counts.unique += usize::from(valid && unique);
+        Ok(())
+    }
+
+    /// Write the counts of the current bucket and reset them.
+    fn write_bucket(&mut self) -> std::io::Result<()> {
+        let Some(bucket) = self.current_bucket else {
+            return Ok(());
+        };
+        let counts = std::mem::take(&mut self.counts);
+        let bucket_start_nanos = bucket * self.bucket_width.as_nanos();
+        let bucket_start =
+            DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_nanos(bucket_start_nanos as u64));
+        match self.format {
+            TimeSeriesFormat::Csv => {
+                let BucketCounts {
+                    total,
+                    valid,
+                    staked,
+                    forwarded,
+                    unique,
+                } = counts;
+                writeln!(
+                    self.writer,
+                    "{bucket_start:?},{total},{valid},{staked},{forwarded},{unique}"
+                )?;
+            }
+            TimeSeriesFormat::Jsonl => {
+                let row = TimeSeriesRow {
+                    bucket_start: format!("{bucket_start:?}"),
+                    counts: &counts,
+                };
+                serde_json::to_writer(&mut self.writer, &row)?;
+                writeln!(self.writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.write_bucket()?;
+        self.writer.flush()
+    }
+}
+
 impl PacketCounter {
     pub fn new(
         start_timestamp: Option<DateTime<Utc>>,
         end_timestamp: Option<DateTime<Utc>>,
+        num_ips_to_report: usize,
+        report_slots: Option<u64>,
+        time_series: Option<PacketTimeSeries>,
     ) -> Self {
         let started = start_timestamp.is_none();
         Self {
             start_timestamp,
             end_timestamp,
             started,
-            done: false,
+            num_ips_to_report,
+            report_slots,
+            slots_since_report: 0,
+            time_series,
+            error: None,
             packet_metrics: PacketMetrics::default(),
         }
     }
 
-    pub fn report(&self, num_ips_to_report: usize) {
+    fn finish(self) -> std::io::Result<()> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.report();
+        match self.time_series {
+            Some(time_series) => time_series.finish(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn report(&self) {
+        let num_ips_to_report = self.num_ips_to_report;
         // destructure packet_metrics
         let PacketMetrics {
             total_count,
@@ -102,6 +386,7 @@ impl PacketCounter {
             tpu_ip_counts,
             fwd_ip_counts,
             signature_set: _,
+            decode_failures,
         } = &self.packet_metrics;
 
         println!("Total packets: {total_count}");
@@ -143,127 +428,73 @@ impl PacketCounter {
         print_top_ips(tpu_ip_counts);
         println!("Top {num_ips_to_report} IPs by FWD packets:");
         print_top_ips(fwd_ip_counts);
+        decode_failures.report(num_ips_to_report);
     }
 
-    pub fn handle_event(&mut self, TimedTracedEvent(timestamp, event): TimedTracedEvent) {
-        if self.done {
-            return;
-        }
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
         let timestamp = DateTime::<Utc>::from(timestamp);
         self.started = self.started
             || self
                 .start_timestamp
                 .map(|start| timestamp >= start)
                 .unwrap_or(true);
-        self.done = self.done
-            || self
-                .end_timestamp
-                .map(|end| timestamp > end)
-                .unwrap_or(false);
+        let done = self
+            .end_timestamp
+            .map(|end| timestamp > end)
+            .unwrap_or(false);
+        if done {
+            return ControlFlow::Break(());
+        }
 
-        if self.started && !self.done {
+        if self.started {
             match event {
                 TracedEvent::PacketBatch(label, packet_batches) => {
-                    self.handle_packet_batches(timestamp, label, packet_batches)
+                    return self.handle_packet_batches(timestamp, label, packet_batches);
                 }
                 TracedEvent::BlockAndBankHash(slot, _, _) => {
                     self.handle_block_and_bank_hash(timestamp, slot)
                 }
             }
         }
+        ControlFlow::Continue(())
     }
 
     fn handle_packet_batches(
         &mut self,
-        _timestamp: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
         label: ChannelLabel,
         packet_batches: BankingPacketBatch,
-    ) {
+    ) -> ControlFlow<()> {
         if matches!(label, ChannelLabel::NonVote) {
             for packet_batch in packet_batches.iter() {
                 for packet in packet_batch {
-                    // Ignore any packet that was filtered by sigverify
-                    self.packet_metrics.total_count += 1;
-
-                    let valid = !packet.meta().discard();
-                    let staked = packet.meta().is_from_staked_node();
-                    let forwarded = packet.meta().forwarded();
-
-                    let unique = if let Some(data) = packet.data(..) {
-                        if let Ok(versioned_transaction) =
-                            bincode::deserialize::<VersionedTransaction>(data)
-                        {
-                            self.packet_metrics
-                                .signature_set
-                                .insert(versioned_transaction.signatures[0])
-                        } else {
-                            false
+                    let properties = self.packet_metrics.record_packet(packet);
+                    if let Some(time_series) = &mut self.time_series {
+                        if let Err(err) = time_series.record_packet(timestamp, properties) {
+                            self.error = Some(err);
+                            return ControlFlow::Break(());
                         }
-                    } else {
-                        false
-                    };
-
-                    self.packet_metrics.valid_count += usize::from(valid);
-                    self.packet_metrics.valid_unique_count += usize::from(valid && unique);
-
-                    self.packet_metrics.tpu_count += usize::from(valid && !forwarded);
-                    self.packet_metrics.fwd_count += usize::from(valid && forwarded);
-
-                    self.packet_metrics.staked_count += usize::from(valid && staked);
-                    self.packet_metrics.staked_tpu_count +=
-                        usize::from(valid && staked && !forwarded);
-                    self.packet_metrics.staked_fwd_count +=
-                        usize::from(valid && staked && forwarded);
-
-                    self.packet_metrics.tpu_unique_count +=
-                        usize::from(valid && !forwarded && unique);
-                    self.packet_metrics.fwd_unique_count +=
-                        usize::from(valid && forwarded && unique);
-
-                    self.packet_metrics.tpu_staked_unique_count +=
-                        usize::from(valid && !forwarded && staked && unique);
-                    self.packet_metrics.fwd_staked_unique_count +=
-                        usize::from(valid && forwarded && staked && unique);
-
-                    let update_ip_counts =
-                        |ip_counts: &mut HashMap<IpAddr, IpPacketCounts>,
-                         ip: IpAddr,
-                         unique: bool,
-                         staked: bool| {
-                            let ip_packet_counts = ip_counts.entry(ip).or_default();
-                            ip_packet_counts.total += 1;
-                            ip_packet_counts.valid += usize::from(valid);
-                            ip_packet_counts.unique += usize::from(valid && unique);
-                            ip_packet_counts.staked += usize::from(valid && staked);
-                        };
-
-                    update_ip_counts(
-                        &mut self.packet_metrics.total_ip_counts,
-                        packet.meta().addr,
-                        unique,
-                        staked,
-                    );
-                    if !forwarded {
-                        update_ip_counts(
-                            &mut self.packet_metrics.tpu_ip_counts,
-                            packet.meta().addr,
-                            unique,
-                            staked,
-                        );
-                    } else {
-                        update_ip_counts(
-                            &mut self.packet_metrics.fwd_ip_counts,
-                            packet.meta().addr,
-                            unique,
-                            staked,
-                        );
                     }
                 }
             }
         }
+        ControlFlow::Continue(())
     }
 
     fn handle_block_and_bank_hash(&mut self, timestamp: DateTime<Utc>, slot: Slot) {
         println!("{timestamp:?} - {slot:?}");
+
+        let Some(report_slots) = self.report_slots else {
+            return;
+        };
+        self.slots_since_report += 1;
+        if self.slots_since_report >= report_slots {
+            self.report();
+            self.packet_metrics = PacketMetrics::default();
+            self.slots_since_report = 0;
+        }
     }
 }
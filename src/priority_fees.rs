@@ -0,0 +1,242 @@
+use {
+    crate::{
+        cli::SlotRange,
+        decode::{decode_and_resolve, DecodeFailureCounts, REPORTED_IP_COUNT},
+        slot_packets::process_slot_range_packets,
+        stats::percentile,
+    },
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    serde::Serialize,
+    solana_address::Address,
+    solana_alt_store::Store,
+    solana_clock::Slot,
+    std::{
+        collections::HashMap,
+        fs::File,
+        io::{BufWriter, Write},
+        path::PathBuf,
+    },
+};
+
+/// Report compute-unit price percentiles for every slot in a slot range, and
+/// for each write-locked account across the range.
+///
+/// Packets are attributed to the slot of the next `BlockAndBankHash`, which is
+/// recorded when that slot ends. If `output` is set, the minimum compute-unit
+/// price of each slot is written as JSON shaped like the
+/// `getRecentPrioritizationFees` RPC result. If `percentiles_output` is set,
+/// the full distributions per slot and per account are written as JSON.
+pub fn priority_fees(
+    event_file_paths: &[PathBuf],
+    slot_range: SlotRange,
+    output: Option<PathBuf>,
+    percentiles_output: Option<PathBuf>,
+    account_count: usize,
+) -> std::io::Result<()> {
+    let mut handler = PriorityFeesHandler::new();
+    process_slot_range_packets(event_file_paths, slot_range, &mut |slot, packet_batches| {
+        handler.handle_slot(slot, packet_batches)
+    })?;
+    let prioritization_fees = handler.prioritization_fees();
+    if let Some(output) = output {
+        write_json(output, &prioritization_fees.rpc_prioritization_fees())?;
+    }
+    if let Some(percentiles_output) = percentiles_output {
+        write_json(percentiles_output, &prioritization_fees)?;
+    }
+    prioritization_fees.report(account_count);
+    handler.decode_failures.report(REPORTED_IP_COUNT);
+    Ok(())
+}
+
+fn write_json(path: PathBuf, value: &impl Serialize) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, value).map_err(std::io::Error::other)?;
+    writer.flush()
+}
+
+struct PriorityFeesHandler {
+    alt_store: Store,
+    slot_priorities: Vec<(Slot, Vec<u64>)>,
+    account_priorities: HashMap<Address, Vec<u64>>,
+    decode_failures: DecodeFailureCounts,
+}
+
+#[derive(Serialize)]
+struct PrioritizationFees {
+    slots: Vec<SlotPrioritizationFee>,
+    accounts: Vec<AccountPrioritizationFee>,
+}
+
+/// An entry of the `getRecentPrioritizationFees` RPC result.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcPrioritizationFee {
+    slot: Slot,
+    prioritization_fee: u64,
+}
+
+#[derive(Serialize)]
+struct SlotPrioritizationFee {
+    slot: Slot,
+    #[serde(flatten)]
+    fees: FeeDistribution,
+}
+
+#[derive(Serialize)]
+struct AccountPrioritizationFee {
+    account: String,
+    #[serde(flatten)]
+    fees: FeeDistribution,
+}
+
+/// Compute-unit price distribution, in micro-lamports.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeDistribution {
+    transaction_count: usize,
+    prioritization_fee: u64,
+    p25: u64,
+    p50: u64,
+    p75: u64,
+    p90: u64,
+    p99: u64,
+}
+
+impl FeeDistribution {
+    fn new(priorities: &mut [u64]) -> Self {
+        priorities.sort_unstable();
+        let percentile = |p| percentile(priorities, p).unwrap_or_default();
+        Self {
+            transaction_count: priorities.len(),
+            prioritization_fee: priorities.first().copied().unwrap_or_default(),
+            p25: percentile(25.0),
+            p50: percentile(50.0),
+            p75: percentile(75.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+        }
+    }
+}
+
+impl std::fmt::Display for FeeDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "transactions={} min={} p25={} p50={} p75={} p90={} p99={}",
+            self.transaction_count,
+            self.prioritization_fee,
+            self.p25,
+            self.p50,
+            self.p75,
+            self.p90,
+            self.p99
+        )
+    }
+}
+
+impl PrioritizationFees {
+    fn rpc_prioritization_fees(&self) -> Vec<RpcPrioritizationFee> {
+        self.slots
+            .iter()
+            .map(
+                |SlotPrioritizationFee { slot, fees }| RpcPrioritizationFee {
+                    slot: *slot,
+                    prioritization_fee: fees.prioritization_fee,
+                },
+            )
+            .collect()
+    }
+
+    pub fn report(&self, account_count: usize) {
+        println!("Slots: {}", self.slots.len());
+        for SlotPrioritizationFee { slot, fees } in &self.slots {
+            println!("  {slot}: {fees}");
+        }
+        println!("Write-locked accounts: {}", self.accounts.len());
+        println!("Top {account_count} write-locked accounts by transactions:");
+        for AccountPrioritizationFee { account, fees } in self.accounts.iter().take(account_count) {
+            println!("  {account}: {fees}");
+        }
+    }
+}
+
+impl PriorityFeesHandler {
+    pub fn new() -> Self {
+        const ALT_STORE_PATH: &str = "alt-store.bin";
+
+        Self {
+            alt_store: Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store"),
+            slot_priorities: Vec::new(),
+            account_priorities: HashMap::new(),
+            decode_failures: DecodeFailureCounts::default(),
+        }
+    }
+
+    /// Decode the packets of an in-range slot, and return the priority of each
+    /// transaction with its write-locked accounts.
+    fn decode_slot_transactions(
+        &mut self,
+        packet_batches: Vec<BankingPacketBatch>,
+    ) -> Vec<(u64, Vec<Address>)> {
+        let mut transactions = Vec::new();
+        for packet in packet_batches
+            .iter()
+            .flat_map(|b| b.iter().flat_map(|b| b.iter()))
+        {
+            let (tx, compute_budget_limits) = match decode_and_resolve(packet, &self.alt_store) {
+                Ok(decoded) => decoded,
+                Err(failure) => {
+                    self.decode_failures.record(packet.meta().addr, failure);
+                    continue;
+                }
+            };
+            let writable = tx
+                .get_account_locks_unchecked()
+                .writable
+                .into_iter()
+                .copied()
+                .collect();
+            transactions.push((compute_budget_limits.compute_unit_price, writable));
+        }
+        transactions
+    }
+
+    fn handle_slot(&mut self, slot: Slot, packet_batches: Vec<BankingPacketBatch>) {
+        let transactions = self.decode_slot_transactions(packet_batches);
+        let mut priorities = Vec::with_capacity(transactions.len());
+        for (priority, writable) in transactions {
+            priorities.push(priority);
+            for account in writable {
+                self.account_priorities
+                    .entry(account)
+                    .or_default()
+                    .push(priority);
+            }
+        }
+        self.slot_priorities.push((slot, priorities));
+    }
+
+    /// Fee distributions per slot in slot order, and per account with the most
+    /// used accounts first.
+    fn prioritization_fees(&mut self) -> PrioritizationFees {
+        let slots = self
+            .slot_priorities
+            .iter_mut()
+            .map(|(slot, priorities)| SlotPrioritizationFee {
+                slot: *slot,
+                fees: FeeDistribution::new(priorities),
+            })
+            .collect();
+        let mut accounts: Vec<_> = self
+            .account_priorities
+            .iter_mut()
+            .map(|(account, priorities)| AccountPrioritizationFee {
+                account: account.to_string(),
+                fees: FeeDistribution::new(priorities),
+            })
+            .collect();
+        accounts.sort_by_key(|account| std::cmp::Reverse(account.fees.transaction_count));
+        PrioritizationFees { slots, accounts }
+    }
+}
@@ -1,39 +1,876 @@
 use {
+    crate::{
+        follow::TraceFollower,
+        seek_index::{SeekIndex, SeekTarget},
+        setup::{
+            get_event_file_paths, is_compressed_event_file, open_event_file, read_timestamp,
+            EventFileSource,
+        },
+    },
+    bincode::Options,
     solana_core::banking_trace::TimedTracedEvent,
-    std::path::{Path, PathBuf},
+    std::{
+        cmp::Reverse,
+        collections::{BinaryHeap, VecDeque},
+        io::{BufRead, BufReader, Read},
+        ops::ControlFlow,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc::{sync_channel, Receiver, SyncSender},
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
 };
 
-#[allow(dead_code)]
+/// Size of the read buffer for each event file. Events are decoded straight
+/// out of this buffer, so memory usage does not depend on the file size.
+const READ_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// Largest record decoded from a compressed event file, whose decompressed
+/// size is not known up front. This keeps corrupt length prefixes from causing
+/// huge allocations.
+pub(crate) const MAX_RECORD_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Most event files decoded at once, each on its own thread. Rotated event
+/// files cover consecutive time ranges, so this is how many files are decoded
+/// ahead while the current one is consumed. More files are only decoded at
+/// once if the merge needs events from all of them, such as when merging
+/// several traces.
+const MAX_DECODE_THREADS: usize = 4;
+
+/// Approximate encoded size of the decoded events sent from a decode thread to
+/// the merge at once.
+const DECODED_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Number of decoded chunks each decode thread may buffer ahead of the merge.
+/// Decoded events take several times their encoded size, so this is kept
+/// small to bound memory usage to a few chunks per file being decoded.
+const DECODED_CHUNKS_PER_FILE: usize = 4;
+
+/// Decode event files ahead on a bounded pool of threads and pass the events
+/// to `handler_fn` in timestamp order. Reading stops as soon as `handler_fn`
+/// breaks.
 pub fn process_event_files(
     event_file_paths: &[PathBuf],
-    handler_fn: &mut impl FnMut(TimedTracedEvent),
+    handler_fn: &mut impl FnMut(TimedTracedEvent) -> ControlFlow<()>,
+) -> std::io::Result<()> {
+    process_events(
+        TraceReader::from_event_file_paths(event_file_paths),
+        handler_fn,
+    )
+}
+
+/// Like [`process_event_files`], but if the trace directory has an up to date
+/// index, skip ahead to `seek_target` instead of reading from the start. The
+/// handler may still see some events before `seek_target`.
+pub fn process_event_files_from(
+    event_file_paths: &[PathBuf],
+    seek_target: SeekTarget,
+    handler_fn: &mut impl FnMut(TimedTracedEvent) -> ControlFlow<()>,
+) -> std::io::Result<()> {
+    let start_offsets = SeekIndex::load_current(event_file_paths)
+        .map(|index| index.start_offsets(seek_target))
+        .unwrap_or_else(|| vec![0; event_file_paths.len()]);
+    process_events(
+        TraceReader::from_event_file_paths_at(event_file_paths, &start_offsets),
+        handler_fn,
+    )
+}
+
+/// Like [`process_event_files`], but for several traces at once, such as
+/// traces collected from different validators in the same cluster. Each trace
+/// is given as its event files. Events from all traces are merged into a single
+/// timestamp-ordered stream and passed to `handler_fn` along with the index of
+/// the trace they came from.
+///
+/// Timestamps are taken from each validator's own clock, so the merged order
+/// is only as accurate as the clocks are synchronized.
+pub fn process_traces(
+    traces: &[Vec<PathBuf>],
+    handler_fn: &mut impl FnMut(usize, TimedTracedEvent) -> ControlFlow<()>,
 ) -> std::io::Result<()> {
-    for event_file_path in event_file_paths {
-        process_event_file(event_file_path, handler_fn)?;
+    let event_file_paths = traces.concat();
+    let trace_indexes: Vec<_> = traces
+        .iter()
+        .enumerate()
+        .flat_map(|(trace_index, event_file_paths)| {
+            std::iter::repeat_n(trace_index, event_file_paths.len())
+        })
+        .collect();
+
+    let mut trace_reader = TraceReader::from_event_file_paths(&event_file_paths);
+    while let Some(result) = trace_reader.next_with_file_index() {
+        let (file_index, event) = result?;
+        if handler_fn(trace_indexes[file_index], event).is_break() {
+            break;
+        }
     }
     Ok(())
 }
 
-fn process_event_file(
-    path: impl AsRef<Path>,
-    handler_fn: &mut impl FnMut(TimedTracedEvent),
+/// Like [`process_event_files`], but follow the live trace directory at
+/// `directory` with a [`TraceFollower`], passing events to `handler_fn` as they
+/// are written. Only returns once `handler_fn` breaks, or on error.
+pub fn follow_event_files(
+    directory: impl AsRef<Path>,
+    handler_fn: &mut impl FnMut(TimedTracedEvent) -> ControlFlow<()>,
 ) -> std::io::Result<()> {
-    let data = std::fs::read(path)?;
+    for event in TraceFollower::open(directory)? {
+        if handler_fn(event?).is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
 
-    // Deserialize events from the buffer
-    let mut offset = 0;
-    while offset < data.len() {
-        match bincode::deserialize::<TimedTracedEvent>(&data[offset..]) {
-            Ok(event) => {
-                // Update the offset to the next event
-                offset += bincode::serialized_size(&event).unwrap() as usize;
-                handler_fn(event);
+fn process_events(
+    trace_reader: TraceReader,
+    handler_fn: &mut impl FnMut(TimedTracedEvent) -> ControlFlow<()>,
+) -> std::io::Result<()> {
+    for event in trace_reader {
+        if handler_fn(event?).is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Call `f` on each event file, on up to [`MAX_DECODE_THREADS`] threads at
+/// once, for work that handles every file independently. The results are in
+/// the order of `event_file_paths`.
+pub fn map_event_files<T: Send>(
+    event_file_paths: &[PathBuf],
+    f: impl Fn(&Path) -> T + Sync,
+) -> Vec<T> {
+    let num_threads = thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(MAX_DECODE_THREADS)
+        .min(event_file_paths.len());
+    let next_index = AtomicUsize::new(0);
+    let mut results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = event_file_paths.get(index) else {
+                            return results;
+                        };
+                        results.push((index, f(path)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("event file thread panicked"))
+            .collect()
+    });
+    results.sort_unstable_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Iterator over the events of a banking trace, in timestamp order.
+///
+/// Event files are decoded on up to [`MAX_DECODE_THREADS`] threads at once,
+/// starting with the files whose events are needed first, and the decoded
+/// events are merged back into a single stream. Events with equal timestamps
+/// are yielded in file order, and the order of events within a file is always
+/// preserved.
+pub struct TraceReader {
+    files: Vec<EventFile>,
+    /// Files that are not being decoded yet, in the order their events are
+    /// needed.
+    to_start: VecDeque<usize>,
+    /// Number of files being decoded that are not done yet.
+    num_decoding: usize,
+    max_decoding: usize,
+    /// Next pending event of each file, keyed by timestamp and file index.
+    /// Files that are not being decoded yet are keyed by the timestamp they
+    /// start at.
+    heads: BinaryHeap<Reverse<(SystemTime, usize)>>,
+    /// Files whose next event must be received before the next merge step.
+    to_fill: Vec<usize>,
+}
+
+struct EventFile {
+    path: PathBuf,
+    start_offset: u64,
+    /// Set once the file is being decoded.
+    decoder: Option<EventFileDecoder>,
+    /// Decoded events received from the decoder and not merged yet.
+    events: std::vec::IntoIter<TimedTracedEvent>,
+    /// Next event of the file, once it is in `heads`.
+    pending: Option<TimedTracedEvent>,
+}
+
+struct EventFileDecoder {
+    receiver: Receiver<std::io::Result<Vec<TimedTracedEvent>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TraceReader {
+    /// Read all event files in the trace directory at `path`.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let event_file_paths = get_event_file_paths(path)?;
+        Ok(Self::from_event_file_paths(&event_file_paths))
+    }
+
+    /// Read the given event files. The files should be ordered by their first
+    /// timestamp, as returned by [`get_event_file_paths`]. Event files of
+    /// several traces may be concatenated, since events are merged by
+    /// timestamp regardless.
+    pub fn from_event_file_paths(event_file_paths: &[PathBuf]) -> Self {
+        Self::from_event_file_paths_at(event_file_paths, &vec![0; event_file_paths.len()])
+    }
+
+    /// Read the given event files, starting each file at the corresponding
+    /// offset in `start_offsets`. Offsets must be at record boundaries, such as
+    /// those from a [`SeekIndex`].
+    pub fn from_event_file_paths_at(event_file_paths: &[PathBuf], start_offsets: &[u64]) -> Self {
+        assert_eq!(event_file_paths.len(), start_offsets.len());
+        let start_timestamps: Vec<_> = event_file_paths
+            .iter()
+            .zip(start_offsets)
+            .map(|(path, offset)| start_timestamp(path, *offset))
+            .collect();
+        let mut to_start: Vec<_> = (0..event_file_paths.len()).collect();
+        to_start.sort_by_key(|index| start_timestamps[*index]);
+
+        let mut trace_reader = Self {
+            files: event_file_paths
+                .iter()
+                .zip(start_offsets)
+                .map(|(path, offset)| EventFile {
+                    path: path.clone(),
+                    start_offset: *offset,
+                    decoder: None,
+                    events: Vec::new().into_iter(),
+                    pending: None,
+                })
+                .collect(),
+            to_start: to_start.into(),
+            num_decoding: 0,
+            max_decoding: thread::available_parallelism()
+                .map_or(1, usize::from)
+                .min(MAX_DECODE_THREADS),
+            heads: start_timestamps
+                .into_iter()
+                .enumerate()
+                .map(|(index, timestamp)| Reverse((timestamp, index)))
+                .collect(),
+            to_fill: Vec::new(),
+        };
+        trace_reader.start_ahead();
+        trace_reader
+    }
+
+    /// Start decoding the file at `index`.
+    fn start(&mut self, index: usize) {
+        let file = &mut self.files[index];
+        file.decoder = Some(EventFileDecoder::spawn(
+            file.path.clone(),
+            file.start_offset,
+        ));
+        self.num_decoding += 1;
+    }
+
+    /// Start decoding the next files whose events are needed, up to the
+    /// maximum number of files decoded at once.
+    fn start_ahead(&mut self) {
+        while self.num_decoding < self.max_decoding {
+            let Some(index) = self.to_start.pop_front() else {
+                break;
+            };
+            if self.files[index].decoder.is_none() {
+                self.start(index);
             }
+        }
+    }
+
+    /// Receive the next event of the file at `index`, if there is one,
+    /// starting to decode the file if it is not being decoded yet.
+    fn fill(&mut self, index: usize) -> std::io::Result<()> {
+        if self.files[index].decoder.is_none() {
+            self.start(index);
+        }
+
+        let file = &mut self.files[index];
+        let event = loop {
+            if let Some(event) = file.events.next() {
+                break event;
+            }
+            let decoder = file
+                .decoder
+                .as_mut()
+                .expect("files are started before they are filled");
+            match decoder.recv()? {
+                Some(events) => file.events = events.into_iter(),
+                None => {
+                    self.num_decoding -= 1;
+                    self.start_ahead();
+                    return Ok(());
+                }
+            }
+        };
+        self.heads.push(Reverse((event.0, index)));
+        file.pending = Some(event);
+        Ok(())
+    }
+
+    /// Like [`Iterator::next`], but also return the index of the event file
+    /// the event was read from.
+    pub fn next_with_file_index(&mut self) -> Option<std::io::Result<(usize, TimedTracedEvent)>> {
+        loop {
+            while let Some(index) = self.to_fill.pop() {
+                if let Err(err) = self.fill(index) {
+                    return Some(Err(err));
+                }
+            }
+
+            let Reverse((_timestamp, index)) = self.heads.pop()?;
+            self.to_fill.push(index);
+            // Files that are not being decoded yet have no pending event, and
+            // are started when filled.
+            if let Some(event) = self.files[index].pending.take() {
+                return Some(Ok((index, event)));
+            }
+        }
+    }
+}
+
+impl Iterator for TraceReader {
+    type Item = std::io::Result<TimedTracedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_file_index()
+            .map(|result| result.map(|(_index, event)| event))
+    }
+}
+
+/// Timestamp of the record at `offset` in the event file at `path`, which the
+/// file is merged at until it is being decoded. Reading a compressed file from
+/// `offset` means decompressing everything before it, so such files are keyed
+/// at `UNIX_EPOCH` to be decoded right away instead. So are files whose
+/// timestamp cannot be read, so their decode thread reports the error.
+fn start_timestamp(path: &Path, offset: u64) -> SystemTime {
+    let read_start_timestamp = || {
+        if offset > 0 && is_compressed_event_file(path)? {
+            return Ok(UNIX_EPOCH);
+        }
+        let EventFileSource { reader, .. } = open_event_file(path, offset)?;
+        read_timestamp(reader)
+    };
+    read_start_timestamp().unwrap_or(UNIX_EPOCH)
+}
+
+impl EventFileDecoder {
+    fn spawn(path: PathBuf, offset: u64) -> Self {
+        let (sender, receiver) = sync_channel(DECODED_CHUNKS_PER_FILE);
+        let handle = thread::Builder::new()
+            .name("traceDecode".to_string())
+            .spawn(move || decode_event_file(path, offset, sender))
+            .expect("failed to spawn decode thread");
+        Self {
+            receiver,
+            handle: Some(handle),
+        }
+    }
+
+    /// Receive the next chunk of decoded events. Returns `None` once the file
+    /// is done.
+    fn recv(&mut self) -> std::io::Result<Option<Vec<TimedTracedEvent>>> {
+        match self.receiver.recv() {
+            Ok(result) => result.map(Some),
             Err(_) => {
-                return Ok(()); // Ignore any trailing bytes
+                // The decode thread hung up, check that it did not panic.
+                if let Some(handle) = self.handle.take() {
+                    if handle.join().is_err() {
+                        return Err(std::io::Error::other("decode thread panicked"));
+                    }
+                }
+                Ok(None)
             }
         }
     }
+}
 
-    Ok(())
+/// Decode events from the file at `path`, starting at `offset`, and send them
+/// to `sender` in chunks until the end of the file, or until the receiver is
+/// dropped. Dropping a `TraceReader` drops the receivers, so decoding stops as
+/// soon as reading is done.
+fn decode_event_file(
+    path: impl AsRef<Path>,
+    offset: u64,
+    sender: SyncSender<std::io::Result<Vec<TimedTracedEvent>>>,
+) {
+    let mut reader = match EventFileReader::open_at(path, offset) {
+        Ok(reader) => reader,
+        Err(err) => {
+            let _ = sender.send(Err(err));
+            return;
+        }
+    };
+
+    let mut chunk = Vec::new();
+    let mut chunk_start = reader.offset();
+    loop {
+        match reader.next_event() {
+            Ok(Some(event)) => chunk.push(event),
+            Ok(None) => {
+                if !chunk.is_empty() {
+                    let _ = sender.send(Ok(chunk));
+                }
+                return;
+            }
+            Err(err) => {
+                // Send the events decoded before the error first.
+                if !chunk.is_empty() && sender.send(Ok(chunk)).is_err() {
+                    return;
+                }
+                let _ = sender.send(Err(err));
+                return;
+            }
+        }
+
+        if reader.offset() - chunk_start >= DECODED_CHUNK_BYTES {
+            if sender.send(Ok(std::mem::take(&mut chunk))).is_err() {
+                return;
+            }
+            chunk_start = reader.offset();
+        }
+    }
+}
+
+/// Streams `TimedTracedEvent`s out of a single event file, decoding each
+/// record exactly once and tracking the byte offset of the next record.
+pub(crate) struct EventFileReader {
+    path: PathBuf,
+    reader: BufReader<Box<dyn Read + Send>>,
+    offset: u64,
+    /// Size of the decompressed file, if it is known up front.
+    file_size: Option<u64>,
+}
+
+/// Result of decoding the next record of an event file.
+enum Record {
+    Event(TimedTracedEvent),
+    End,
+    Undecodable(bincode::Error),
+}
+
+impl EventFileReader {
+    pub(crate) fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::open_at(path, 0)
+    }
+
+    /// Open the file and start reading at `offset`.
+    pub(crate) fn open_at(path: impl AsRef<Path>, offset: u64) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let EventFileSource { reader, size } = open_event_file(path, offset)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::with_capacity(READ_BUFFER_SIZE, reader),
+            offset,
+            file_size: size,
+        })
+    }
+
+    /// Byte offset of the next record in the file.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Decode the next record. The offset is only advanced past records that
+    /// were successfully decoded.
+    fn next_record(&mut self) -> std::io::Result<Record> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(Record::End);
+        }
+
+        // A record can never extend past the end of the file. Limiting the
+        // decoder also keeps corrupt length prefixes from causing huge
+        // allocations.
+        let options = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(
+                self.file_size
+                    .map(|file_size| file_size.saturating_sub(self.offset))
+                    .unwrap_or(MAX_RECORD_SIZE),
+            );
+        let mut reader = CountingReader::new(&mut self.reader);
+        match options.deserialize_from::<_, TimedTracedEvent>(&mut reader) {
+            Ok(event) => {
+                self.offset += reader.count;
+                Ok(Record::Event(event))
+            }
+            Err(err) => match *err {
+                bincode::ErrorKind::Io(err) if err.kind() != std::io::ErrorKind::UnexpectedEof => {
+                    Err(err)
+                }
+                err => Ok(Record::Undecodable(Box::new(err))),
+            },
+        }
+    }
+
+    /// Decode the next event. Returns `None` at the end of the file, or at the
+    /// first undecodable record, after logging a warning.
+    pub(crate) fn next_event(&mut self) -> std::io::Result<Option<TimedTracedEvent>> {
+        match self.next_record()? {
+            Record::Event(event) => Ok(Some(event)),
+            Record::End => Ok(None),
+            Record::Undecodable(err) => {
+                let trailing_bytes = match self.file_size {
+                    Some(file_size) => format!("{} trailing bytes", file_size - self.offset),
+                    None => "trailing bytes".to_string(),
+                };
+                eprintln!(
+                    "Warning: undecodable record in {} at offset {} ({trailing_bytes} ignored): {}",
+                    self.path.display(),
+                    self.offset,
+                    describe_decode_error(&err),
+                );
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Describe a decode error, calling out records cut off by the end of the file.
+fn describe_decode_error(err: &bincode::Error) -> String {
+    match **err {
+        // Only `UnexpectedEof` is reported as an undecodable record.
+        bincode::ErrorKind::SizeLimit | bincode::ErrorKind::Io(_) => {
+            "record extends past the end of the file".to_string()
+        }
+        _ => err.to_string(),
+    }
+}
+
+/// Summary of decoding a single event file.
+pub struct EventFileReport {
+    pub path: PathBuf,
+    /// Size of the decompressed file.
+    pub file_size: u64,
+    pub num_events: usize,
+    pub first_timestamp: Option<SystemTime>,
+    pub last_timestamp: Option<SystemTime>,
+    /// Regions that could not be decoded, in file order.
+    pub bad_regions: Vec<BadRegion>,
+}
+
+/// A region of an event file that could not be decoded.
+pub struct BadRegion {
+    /// Offset of the first record that could not be decoded.
+    pub offset: u64,
+    /// Number of bytes from `offset` to the end of the file.
+    pub trailing_bytes: u64,
+    /// Decode error for the record at `offset`.
+    pub error: String,
+    /// Offset of the next decodable record after `offset`, if any.
+    pub resync_offset: Option<u64>,
+}
+
+/// Decode every record in the event file at `path` and report any regions
+/// that could not be decoded. If `keep_going` is set, decoding resumes at the
+/// next decodable record after each bad region. Otherwise, decoding stops at
+/// the first bad region.
+pub fn verify_event_file(
+    path: impl AsRef<Path>,
+    keep_going: bool,
+) -> std::io::Result<EventFileReport> {
+    let path = path.as_ref();
+    let mut reader = EventFileReader::open(path)?;
+    let mut report = EventFileReport {
+        path: path.to_path_buf(),
+        file_size: 0,
+        num_events: 0,
+        first_timestamp: None,
+        last_timestamp: None,
+        bad_regions: Vec::new(),
+    };
+
+    loop {
+        match reader.next_record()? {
+            Record::Event(TimedTracedEvent(timestamp, _)) => {
+                report.num_events += 1;
+                report.first_timestamp.get_or_insert(timestamp);
+                report.last_timestamp = Some(timestamp);
+            }
+            Record::End => {
+                report.file_size = reader.offset();
+                break;
+            }
+            Record::Undecodable(err) => {
+                let offset = reader.offset();
+                let trailing_bytes = remaining_bytes(path, offset)?;
+                report.file_size = offset + trailing_bytes;
+                let resync_offset = find_next_record(path, offset + 1, report.last_timestamp)?;
+                report.bad_regions.push(BadRegion {
+                    offset,
+                    trailing_bytes,
+                    error: describe_decode_error(&err),
+                    resync_offset,
+                });
+
+                match resync_offset {
+                    Some(resync_offset) if keep_going => {
+                        reader = EventFileReader::open_at(path, resync_offset)?;
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Size of the fixed record header: the timestamp (`u64` seconds and `u32`
+/// nanoseconds) followed by the `u32` variant index of the `TracedEvent`.
+const RECORD_HEADER_SIZE: usize = 16;
+
+/// How far a resync candidate's timestamp may be from the last good timestamp.
+const RESYNC_TIMESTAMP_TOLERANCE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Scan forward from `start` for the first offset at which a full record can
+/// be decoded. Candidates are first filtered on a plausible record header, with
+/// a timestamp close to `last_timestamp` if one is known, and then decoded out
+/// of an in-memory window of the file.
+fn find_next_record(
+    path: &Path,
+    start: u64,
+    last_timestamp: Option<SystemTime>,
+) -> std::io::Result<Option<u64>> {
+    let timestamp_range = match last_timestamp {
+        Some(timestamp) => {
+            timestamp
+                .checked_sub(RESYNC_TIMESTAMP_TOLERANCE)
+                .unwrap_or(UNIX_EPOCH)..=timestamp + RESYNC_TIMESTAMP_TOLERANCE
+        }
+        None => UNIX_EPOCH..=SystemTime::now() + RESYNC_TIMESTAMP_TOLERANCE,
+    };
+    let is_plausible_header = |header: &[u8]| {
+        let secs = u64::from_le_bytes(header[0..8].try_into().unwrap());
+        let nanos = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let variant = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        nanos < 1_000_000_000
+            && variant < 2
+            && UNIX_EPOCH
+                .checked_add(Duration::new(secs, nanos))
+                .is_some_and(|timestamp| timestamp_range.contains(&timestamp))
+    };
+
+    let EventFileSource { mut reader, .. } = open_event_file(path, start)?;
+    let mut window = vec![0; READ_BUFFER_SIZE];
+    let mut window_start = start;
+    let mut window_len = 0;
+    loop {
+        let bytes_read = read_up_to(&mut reader, &mut window[window_len..])?;
+        window_len += bytes_read;
+        let is_end_of_file = window_len < window.len();
+
+        let mut index = 0;
+        while index + RECORD_HEADER_SIZE <= window_len {
+            if is_plausible_header(&window[index..index + RECORD_HEADER_SIZE]) {
+                match decode_candidate(&window[index..window_len]) {
+                    Candidate::Record => return Ok(Some(window_start + index as u64)),
+                    Candidate::NotARecord => {}
+                    // Cut off by the end of the file.
+                    Candidate::CutOff if is_end_of_file => {}
+                    // Larger than the whole window, so decode it from the file.
+                    Candidate::CutOff if index == 0 => {
+                        let candidate = window_start;
+                        let mut reader = EventFileReader::open_at(path, candidate)?;
+                        if let Record::Event(_) = reader.next_record()? {
+                            return Ok(Some(candidate));
+                        }
+                    }
+                    // Move the candidate to the start of the window, and
+                    // check it again once the rest of it is read.
+                    Candidate::CutOff => break,
+                }
+            }
+            index += 1;
+        }
+
+        if is_end_of_file {
+            return Ok(None);
+        }
+
+        // Keep the rest of the window, so candidates spanning a window
+        // boundary are checked.
+        window.copy_within(index..window_len, 0);
+        window_start += index as u64;
+        window_len -= index;
+    }
+}
+
+/// Result of decoding a resync candidate out of the scan window.
+enum Candidate {
+    Record,
+    NotARecord,
+    /// The candidate extends past the end of the window.
+    CutOff,
+}
+
+/// Decode a record from the start of `bytes`.
+fn decode_candidate(bytes: &[u8]) -> Candidate {
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(bytes.len() as u64);
+    match options.deserialize::<TimedTracedEvent>(bytes) {
+        Ok(_) => Candidate::Record,
+        Err(err) => match *err {
+            bincode::ErrorKind::SizeLimit | bincode::ErrorKind::Io(_) => Candidate::CutOff,
+            _ => Candidate::NotARecord,
+        },
+    }
+}
+
+/// Read into `buf` until it is full or the end of the reader is reached.
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut total_bytes_read = 0;
+    while total_bytes_read < buf.len() {
+        match reader.read(&mut buf[total_bytes_read..]) {
+            Ok(0) => break,
+            Ok(bytes_read) => total_bytes_read += bytes_read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total_bytes_read)
+}
+
+/// Number of bytes in the event file at `path` from `offset` to the end.
+fn remaining_bytes(path: &Path, offset: u64) -> std::io::Result<u64> {
+    let EventFileSource { mut reader, size } = open_event_file(path, offset)?;
+    match size {
+        Some(size) => Ok(size.saturating_sub(offset)),
+        None => std::io::copy(&mut reader, &mut std::io::sink()),
+    }
+}
+
+/// Wraps a reader and counts the bytes read through it.
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R> CountingReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.count += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::TraceWriter, solana_core::banking_trace::TracedEvent, solana_hash::Hash,
+    };
+
+    /// Write a trace with a `BlockAndBankHash` for each slot, at the given
+    /// second, rotating the event file after every event.
+    fn write_trace(directory: &Path, slot_seconds: &[(u64, u64)]) -> Vec<PathBuf> {
+        let mut writer = TraceWriter::create(directory, 1).unwrap();
+        for (slot, seconds) in slot_seconds {
+            let event = TracedEvent::BlockAndBankHash(*slot, Hash::default(), Hash::default());
+            let timestamp = UNIX_EPOCH + Duration::from_secs(*seconds);
+            writer
+                .write_event(&TimedTracedEvent(timestamp, event))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+        get_event_file_paths(directory).unwrap()
+    }
+
+    fn read_slots(trace_reader: TraceReader) -> Vec<u64> {
+        trace_reader
+            .map(|event| match event.unwrap().1 {
+                TracedEvent::BlockAndBankHash(slot, _, _) => slot,
+                TracedEvent::PacketBatch(_, _) => panic!("unexpected packet batch"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trace_reader_merges_rotated_files_and_traces() {
+        let directory = std::env::temp_dir().join(format!("process-test-{}", std::process::id()));
+        // More files than are decoded at once.
+        let first_trace = write_trace(
+            &directory.join("first"),
+            &[(1, 1), (3, 3), (5, 5), (7, 7), (9, 9), (11, 11)],
+        );
+        let second_trace = write_trace(&directory.join("second"), &[(2, 2), (4, 4), (6, 6)]);
+        assert_eq!(first_trace.len(), 6);
+
+        assert_eq!(
+            read_slots(TraceReader::from_event_file_paths(&first_trace)),
+            [1, 3, 5, 7, 9, 11]
+        );
+        assert_eq!(
+            read_slots(TraceReader::from_event_file_paths(
+                &[first_trace, second_trace].concat()
+            )),
+            [1, 2, 3, 4, 5, 6, 7, 9, 11]
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_map_event_files_keeps_file_order() {
+        let event_file_paths: Vec<_> = (0..3 * MAX_DECODE_THREADS)
+            .map(|index| PathBuf::from(format!("events.{index}")))
+            .collect();
+        let results = map_event_files(&event_file_paths, Path::to_path_buf);
+        assert_eq!(results, event_file_paths);
+    }
+
+    #[test]
+    fn test_verify_event_file_resyncs_after_garbage() {
+        let directory = std::env::temp_dir().join(format!("verify-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let events: Vec<_> = (1..=3)
+            .map(|slot| {
+                let event = TracedEvent::BlockAndBankHash(slot, Hash::default(), Hash::default());
+                TimedTracedEvent(UNIX_EPOCH + Duration::from_secs(slot), event)
+            })
+            .collect();
+        let first_record = bincode::serialize(&events[0]).unwrap();
+        let garbage = [0xff; 7];
+        let mut file_data = first_record.clone();
+        file_data.extend(garbage);
+        for event in &events[1..] {
+            file_data.extend(bincode::serialize(event).unwrap());
+        }
+        let path = directory.join("events");
+        std::fs::write(&path, &file_data).unwrap();
+
+        let report = verify_event_file(&path, true).unwrap();
+        assert_eq!(report.num_events, 3);
+        assert_eq!(report.file_size, file_data.len() as u64);
+        assert_eq!(report.bad_regions.len(), 1);
+        let bad_region = &report.bad_regions[0];
+        assert_eq!(bad_region.offset, first_record.len() as u64);
+        assert_eq!(
+            bad_region.resync_offset,
+            Some((first_record.len() + garbage.len()) as u64)
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
 }
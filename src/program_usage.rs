@@ -0,0 +1,122 @@
+use {
+    crate::{
+        cli::SlotRange,
+        decode::{decode_and_resolve, DecodeFailureCounts, REPORTED_IP_COUNT},
+        slot_packets::process_slot_range_packets,
+        stats::percentile,
+    },
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    solana_address::Address,
+    solana_alt_store::Store,
+    std::{
+        collections::{HashMap, HashSet},
+        path::PathBuf,
+    },
+};
+
+/// Report per program invocation counts, transaction counts, requested CUs
+/// and priority distribution, for the non-vote transactions in a slot range.
+///
+/// Packets are attributed to the slot of the next `BlockAndBankHash`, which is
+/// recorded when that slot ends.
+pub fn program_usage(event_file_paths: &[PathBuf], slot_range: SlotRange) -> std::io::Result<()> {
+    let mut handler = ProgramUsageHandler::new();
+    process_slot_range_packets(
+        event_file_paths,
+        slot_range,
+        &mut |_slot, packet_batches| {
+            for packet_batches in packet_batches {
+                handler.handle_packet_batches(packet_batches);
+            }
+        },
+    )?;
+    handler.report();
+    Ok(())
+}
+
+struct ProgramUsageHandler {
+    alt_store: Store,
+    program_usage_statistics: HashMap<Address, ProgramUsageStatistics>,
+    decode_failures: DecodeFailureCounts,
+}
+
+#[derive(Default)]
+struct ProgramUsageStatistics {
+    num_invocations: usize,
+    num_transactions: usize,
+    /// Requested CUs of the transactions invoking the program.
+    sum_requested_cus: u64,
+    /// Priority of each transaction invoking the program.
+    priorities: Vec<u64>,
+}
+
+impl ProgramUsageHandler {
+    pub fn new() -> Self {
+        const ALT_STORE_PATH: &str = "alt-store.bin";
+
+        Self {
+            alt_store: Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store"),
+            program_usage_statistics: HashMap::new(),
+            decode_failures: DecodeFailureCounts::default(),
+        }
+    }
+
+    fn handle_packet_batches(&mut self, packet_batches: BankingPacketBatch) {
+        for packet in packet_batches.iter().flat_map(|b| b.iter()) {
+            let (tx, compute_budget_limits) = match decode_and_resolve(packet, &self.alt_store) {
+                Ok(decoded) => decoded,
+                Err(failure) => {
+                    self.decode_failures.record(packet.meta().addr, failure);
+                    continue;
+                }
+            };
+
+            let mut programs = HashSet::new();
+            for (program, _) in tx.message().program_instructions_iter() {
+                self.program_usage_statistics
+                    .entry(*program)
+                    .or_default()
+                    .num_invocations += 1;
+                programs.insert(*program);
+            }
+            for program in programs {
+                let statistics = self.program_usage_statistics.entry(program).or_default();
+                statistics.num_transactions += 1;
+                statistics.sum_requested_cus += u64::from(compute_budget_limits.compute_unit_limit);
+                statistics
+                    .priorities
+                    .push(compute_budget_limits.compute_unit_price);
+            }
+        }
+    }
+
+    /// Report program usage statistics, most invoked programs first:
+    /// - Number of invocations and transactions
+    /// - Total requested CUs of those transactions
+    /// - Priority min, p50, p90, p99, max
+    pub fn report(&mut self) {
+        let mut program_usage_statistics: Vec<_> =
+            self.program_usage_statistics.iter_mut().collect();
+        program_usage_statistics.sort_by_key(|(_, s)| std::cmp::Reverse(s.num_invocations));
+
+        println!("Total unique programs: {}", program_usage_statistics.len());
+        for (program, statistics) in program_usage_statistics {
+            statistics.priorities.sort_unstable();
+            let priorities = &statistics.priorities;
+            println!(
+                "{program}: invocations={} transactions={} requested_cus={} priority: [{}, {}, \
+                 {}, {}, {}]",
+                statistics.num_invocations,
+                statistics.num_transactions,
+                statistics.sum_requested_cus,
+                priorities.first().copied().unwrap_or_default(),
+                percentile(priorities, 50.0).unwrap_or_default(),
+                percentile(priorities, 90.0).unwrap_or_default(),
+                percentile(priorities, 99.0).unwrap_or_default(),
+                priorities.last().copied().unwrap_or_default(),
+            );
+        }
+
+        self.decode_failures.report(REPORTED_IP_COUNT);
+    }
+}
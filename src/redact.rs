@@ -0,0 +1,214 @@
+use {
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{process::process_event_files, TraceWriter},
+    solana_address::Address,
+    solana_core::banking_trace::{TimedTracedEvent, TracedEvent},
+    solana_perf::packet::{BytesPacket, BytesPacketBatch, PacketBatch},
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        collections::{HashMap, HashSet},
+        net::{IpAddr, Ipv6Addr},
+        ops::ControlFlow,
+        path::PathBuf,
+        sync::Arc,
+    },
+};
+
+/// Context string for deriving the pseudonym key from the user's key.
+const PSEUDONYM_KEY_CONTEXT: &str = "banking-trace-tool 2024-02-01 redact ip pseudonym";
+
+/// Environment variable holding the pseudonym key, if no key file is given.
+const KEY_ENV_VAR: &str = "BANKING_TRACE_REDACT_KEY";
+
+pub fn redact(
+    event_file_paths: &[PathBuf],
+    output: PathBuf,
+    key_file: Option<PathBuf>,
+    zero_port: bool,
+    strip_signers: Option<HashSet<Address>>,
+    max_file_size: u64,
+) -> std::io::Result<()> {
+    let key = read_key(key_file)?;
+    let writer = TraceWriter::create(&output, max_file_size)?;
+    let mut handler = Redactor::new(writer, &key, zero_port, strip_signers);
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    handler.finish(output)
+}
+
+/// Read the pseudonym key from `key_file`, or from the environment. The key is
+/// never taken on the command line, where it would be visible to other users
+/// and kept in shell history.
+fn read_key(key_file: Option<PathBuf>) -> std::io::Result<String> {
+    let key = match key_file {
+        Some(key_file) => std::fs::read_to_string(key_file)?,
+        None => std::env::var(KEY_ENV_VAR)
+            .map_err(|_| std::io::Error::other(format!("Pass --key-file or set {KEY_ENV_VAR}")))?,
+    };
+    let key = key.trim_end_matches(['\r', '\n']);
+    if key.is_empty() {
+        return Err(std::io::Error::other("The redaction key is empty"));
+    }
+    Ok(key.to_owned())
+}
+
+struct Redactor {
+    writer: TraceWriter,
+    pseudonyms: IpPseudonyms,
+    zero_port: bool,
+    strip_signers: Option<HashSet<Address>>,
+    /// First write error, which stops processing.
+    error: Option<std::io::Error>,
+
+    num_events: usize,
+    num_stripped_packets: usize,
+}
+
+/// Maps IPs to pseudonymous IPs with a keyed hash, so the same IP always maps
+/// to the same pseudonym under the same key, and the real IP cannot be
+/// recovered without the key.
+///
+/// Pseudonyms are IPv6 unique local addresses (`fd00::/8`), for IPv4 and IPv6
+/// alike. Mapping into the 120 bits of that range makes collisions between
+/// distinct IPs vanishingly unlikely, so per-IP statistics are preserved,
+/// which would not be true when mapping into the 32 bits of IPv4.
+struct IpPseudonyms {
+    key: [u8; 32],
+    cache: HashMap<IpAddr, IpAddr>,
+}
+
+impl IpPseudonyms {
+    fn new(key: &str) -> Self {
+        Self {
+            key: blake3::derive_key(PSEUDONYM_KEY_CONTEXT, key.as_bytes()),
+            cache: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, ip: IpAddr) -> IpAddr {
+        *self.cache.entry(ip).or_insert_with(|| {
+            // Hash the IPv6 form, so an IPv4 address and its IPv4-mapped IPv6
+            // address get the same pseudonym.
+            let ip = match ip {
+                IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+                IpAddr::V6(ip) => ip,
+            };
+            let hash = blake3::keyed_hash(&self.key, &ip.octets());
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&hash.as_bytes()[..16]);
+            octets[0] = 0xfd;
+            IpAddr::V6(Ipv6Addr::from(octets))
+        })
+    }
+}
+
+impl Redactor {
+    pub fn new(
+        writer: TraceWriter,
+        key: &str,
+        zero_port: bool,
+        strip_signers: Option<HashSet<Address>>,
+    ) -> Self {
+        Self {
+            writer,
+            pseudonyms: IpPseudonyms::new(key),
+            zero_port,
+            strip_signers,
+            error: None,
+            num_events: 0,
+            num_stripped_packets: 0,
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        let event = match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                TracedEvent::PacketBatch(label, self.redact_packet_batches(packet_batches))
+            }
+            event @ TracedEvent::BlockAndBankHash(_, _, _) => event,
+        };
+
+        match self.writer.write_event(&TimedTracedEvent(timestamp, event)) {
+            Ok(()) => {
+                self.num_events += 1;
+                ControlFlow::Continue(())
+            }
+            Err(err) => {
+                self.error = Some(err);
+                ControlFlow::Break(())
+            }
+        }
+    }
+
+    /// Rebuild the packet batches with redacted metadata, dropping packets
+    /// signed by any of the stripped signers. Empty batches are kept, so batch
+    /// counts are unchanged.
+    fn redact_packet_batches(&mut self, packet_batches: BankingPacketBatch) -> BankingPacketBatch {
+        let redacted_packet_batches = packet_batches
+            .iter()
+            .map(|packet_batch| {
+                let mut redacted_packet_batch = BytesPacketBatch::with_capacity(packet_batch.len());
+                for packet in packet_batch {
+                    let mut packet = packet.to_bytes_packet();
+                    if self.is_stripped(&mut packet) {
+                        self.num_stripped_packets += 1;
+                        continue;
+                    }
+
+                    let meta = packet.meta_mut();
+                    meta.addr = self.pseudonyms.get(meta.addr);
+                    if self.zero_port {
+                        meta.port = 0;
+                    }
+                    redacted_packet_batch.push(packet);
+                }
+                PacketBatch::from(redacted_packet_batch)
+            })
+            .collect();
+        Arc::new(redacted_packet_batches)
+    }
+
+    /// Whether the packet holds a transaction signed by a stripped signer.
+    /// Discarded packets are checked too, since their payload is still copied.
+    fn is_stripped(&self, packet: &mut BytesPacket) -> bool {
+        let Some(strip_signers) = &self.strip_signers else {
+            return false;
+        };
+        // `data` is `None` for discarded packets, so clear the flag while
+        // reading the payload.
+        let discard = packet.meta().discard();
+        packet.meta_mut().set_discard(false);
+        let transaction = packet
+            .data(..)
+            .and_then(|data| bincode::deserialize::<VersionedTransaction>(data).ok());
+        packet.meta_mut().set_discard(discard);
+        let Some(transaction) = transaction else {
+            return false;
+        };
+
+        let num_signers = usize::from(transaction.message.header().num_required_signatures);
+        transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .take(num_signers)
+            .any(|signer| strip_signers.contains(signer))
+    }
+
+    pub fn finish(self, output: PathBuf) -> std::io::Result<()> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.writer.finish()?;
+        println!(
+            "Wrote {} events to {}, mapped {} IPs, stripped {} packets",
+            self.num_events,
+            output.display(),
+            self.pseudonyms.cache.len(),
+            self.num_stripped_packets
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use {
+    crate::stats::format_duration_percentiles,
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::process::process_event_files,
+    solana_clock::Slot,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        collections::{HashMap, HashSet},
+        fs::File,
+        io::{BufWriter, Write},
+        net::IpAddr,
+        ops::ControlFlow,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    },
+};
+
+/// Upper bounds of the copies-per-signature histogram buckets.
+const COPIES_BUCKETS: [usize; 5] = [1, 2, 5, 10, 100];
+
+/// Report how often each non-vote transaction signature was received, from
+/// how many IPs and over how long, and which IPs resend the most.
+///
+/// Each copy is assigned the slot after the last `BlockAndBankHash`, since
+/// the marker of a slot is recorded as the next slot starts.
+pub fn resends(
+    event_file_paths: &[PathBuf],
+    output: Option<PathBuf>,
+    ip_count: usize,
+) -> std::io::Result<()> {
+    let mut handler = ResendsHandler::default();
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    if let Some(output) = output {
+        handler.write_csv(output)?;
+    }
+    handler.report(ip_count);
+    Ok(())
+}
+
+#[derive(Default)]
+struct ResendsHandler {
+    /// Slot after the last `BlockAndBankHash`.
+    current_slot: Option<Slot>,
+    signatures: HashMap<Signature, SignatureCopies>,
+    ips: HashMap<IpAddr, IpResendCounts>,
+}
+
+struct SignatureCopies {
+    first: SystemTime,
+    last: SystemTime,
+    tpu_copies: usize,
+    fwd_copies: usize,
+    ips: HashSet<IpAddr>,
+    /// Arrival slot of each copy, in arrival order.
+    slots: Vec<Option<Slot>>,
+}
+
+#[derive(Default)]
+struct IpResendCounts {
+    copies: usize,
+    /// Copies of signatures that had already been received from any IP.
+    duplicates: usize,
+    /// Copies of signatures that had already been received from this IP.
+    resends: usize,
+}
+
+impl SignatureCopies {
+    fn num_copies(&self) -> usize {
+        self.tpu_copies + self.fwd_copies
+    }
+
+    fn spread(&self) -> Duration {
+        self.last.duration_since(self.first).unwrap_or_default()
+    }
+}
+
+impl ResendsHandler {
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                self.handle_packet_batches(timestamp, label, packet_batches)
+            }
+            TracedEvent::BlockAndBankHash(slot, _, _) => self.current_slot = Some(slot + 1),
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn handle_packet_batches(
+        &mut self,
+        timestamp: SystemTime,
+        label: ChannelLabel,
+        packet_batches: BankingPacketBatch,
+    ) {
+        if !matches!(label, ChannelLabel::NonVote) {
+            return;
+        }
+
+        for packet in packet_batches.iter().flat_map(|b| b.iter()) {
+            let Some(signature) = packet
+                .data(..)
+                .and_then(|data| bincode::deserialize::<VersionedTransaction>(data).ok())
+                .and_then(|transaction| transaction.signatures.first().copied())
+            else {
+                continue;
+            };
+            let ip = packet.meta().addr;
+            let forwarded = packet.meta().forwarded();
+
+            let ip_counts = self.ips.entry(ip).or_default();
+            ip_counts.copies += 1;
+            let copies = match self.signatures.get_mut(&signature) {
+                Some(copies) => {
+                    ip_counts.duplicates += 1;
+                    ip_counts.resends += usize::from(copies.ips.contains(&ip));
+                    copies.first = copies.first.min(timestamp);
+                    copies.last = copies.last.max(timestamp);
+                    copies
+                }
+                None => self.signatures.entry(signature).or_insert(SignatureCopies {
+                    first: timestamp,
+                    last: timestamp,
+                    tpu_copies: 0,
+                    fwd_copies: 0,
+                    ips: HashSet::new(),
+                    slots: Vec::new(),
+                }),
+            };
+            if forwarded {
+                copies.fwd_copies += 1;
+            } else {
+                copies.tpu_copies += 1;
+            }
+            copies.ips.insert(ip);
+            copies.slots.push(self.current_slot);
+        }
+    }
+
+    /// Write a row per signature, with the arrival slot of each copy.
+    fn write_csv(&self, output: PathBuf) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(output)?);
+        writeln!(
+            writer,
+            "signature,copies,distinct_ips,spread_us,tpu_copies,fwd_copies,slots"
+        )?;
+        for (signature, copies) in &self.signatures {
+            let slots: Vec<_> = copies
+                .slots
+                .iter()
+                .map(|slot| slot.map_or("-".to_string(), |slot| slot.to_string()))
+                .collect();
+            writeln!(
+                writer,
+                "{signature},{},{},{},{},{},{}",
+                copies.num_copies(),
+                copies.ips.len(),
+                copies.spread().as_micros(),
+                copies.tpu_copies,
+                copies.fwd_copies,
+                slots.join(" "),
+            )?;
+        }
+        writer.flush()
+    }
+
+    pub fn report(&self, ip_count: usize) {
+        let num_copies: usize = self.signatures.values().map(|c| c.num_copies()).sum();
+        println!("Unique signatures: {}", self.signatures.len());
+        println!("Total copies: {num_copies}");
+
+        println!("Copies per signature:");
+        let mut lower = 1;
+        for upper in COPIES_BUCKETS {
+            let count = self
+                .signatures
+                .values()
+                .filter(|copies| (lower..=upper).contains(&copies.num_copies()))
+                .count();
+            println!("  {lower}-{upper}: {count}");
+            lower = upper + 1;
+        }
+        let count = self
+            .signatures
+            .values()
+            .filter(|copies| copies.num_copies() >= lower)
+            .count();
+        println!("  {lower}+: {count}");
+
+        let resent: Vec<_> = self
+            .signatures
+            .values()
+            .filter(|copies| copies.num_copies() > 1)
+            .collect();
+        let from_multiple_ips = resent.iter().filter(|c| c.ips.len() > 1).count();
+        let over_multiple_slots = resent
+            .iter()
+            .filter(|copies| copies.slots.iter().any(|slot| *slot != copies.slots[0]))
+            .count();
+        let with_forwarded = resent.iter().filter(|c| c.fwd_copies > 0).count();
+        println!(
+            "Signatures with multiple copies: {} from_multiple_ips={from_multiple_ips} \
+             over_multiple_slots={over_multiple_slots} with_forwarded_copies={with_forwarded}",
+            resent.len()
+        );
+        let mut spreads: Vec<_> = resent.iter().map(|copies| copies.spread()).collect();
+        spreads.sort();
+        println!(
+            "  first to last copy: {}",
+            format_duration_percentiles(&spreads)
+        );
+
+        let mut ips: Vec<_> = self.ips.iter().collect();
+        ips.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.resends));
+        println!("Top {ip_count} IPs by resends:");
+        for (ip, counts) in ips.into_iter().take(ip_count) {
+            println!(
+                "  {ip}: copies={} duplicates={} resends={}",
+                counts.copies, counts.duplicates, counts.resends
+            );
+        }
+    }
+}
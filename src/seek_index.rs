@@ -0,0 +1,211 @@
+use {
+    crate::process::{map_event_files, EventFileReader},
+    serde::{Deserialize, Serialize},
+    solana_clock::Slot,
+    solana_core::banking_trace::{TimedTracedEvent, TracedEvent},
+    std::{
+        fs::File,
+        io::{BufReader, BufWriter},
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Name of the index file, written next to the event files. For an archived
+/// trace, this is next to the extracted event files.
+pub const SEEK_INDEX_FILENAME: &str = "events.index";
+
+/// Width of the timestamp buckets recorded in the index.
+const TIMESTAMP_BUCKET: Duration = Duration::from_secs(1);
+
+/// Where to start reading a trace from.
+#[derive(Clone, Copy, Debug)]
+pub enum SeekTarget {
+    /// Start with the events that follow the `BlockAndBankHash` of the
+    /// previous slot in the trace.
+    Slot(Slot),
+    /// Start with the events at or after the timestamp.
+    Timestamp(SystemTime),
+}
+
+/// Sidecar index of a trace directory, mapping each `BlockAndBankHash` slot and
+/// each timestamp bucket to a byte offset in an event file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeekIndex {
+    /// Indexed event files, in the order returned by `get_event_file_paths`.
+    pub files: Vec<IndexedEventFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexedEventFile {
+    pub file_name: PathBuf,
+    /// Size of the file on disk when it was indexed, used to detect a stale
+    /// index.
+    pub file_size: u64,
+    /// Checkpoints in file order.
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Timestamp of the event at `offset`.
+    pub timestamp: SystemTime,
+    /// Byte offset of the event in the file.
+    pub offset: u64,
+    /// Slot of the event, if it is a `BlockAndBankHash`.
+    pub slot: Option<Slot>,
+}
+
+impl SeekIndex {
+    /// Path of the index file for the given event files. Returns `None` if the
+    /// event files do not all belong to the same trace, since there is no
+    /// single index covering them.
+    pub fn path(event_file_paths: &[PathBuf]) -> Option<PathBuf> {
+        let index_path = Self::event_file_index_path(event_file_paths.first()?)?;
+        event_file_paths[1..]
+            .iter()
+            .all(|path| Self::event_file_index_path(path).as_ref() == Some(&index_path))
+            .then_some(index_path)
+    }
+
+    fn event_file_index_path(event_file_path: &Path) -> Option<PathBuf> {
+        Some(event_file_path.parent()?.join(SEEK_INDEX_FILENAME))
+    }
+
+    /// Index the given event files, decoding several files at once.
+    pub fn build(event_file_paths: &[PathBuf]) -> std::io::Result<Self> {
+        let files = map_event_files(event_file_paths, index_event_file)
+            .into_iter()
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Ok(Self { files })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        bincode::deserialize_from(file).map_err(std::io::Error::other)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(file, self).map_err(std::io::Error::other)
+    }
+
+    /// Load the index for the given event files, if there is one and it is
+    /// up to date. Problems with the index are logged and otherwise ignored,
+    /// since the trace can always be read without it.
+    pub fn load_current(event_file_paths: &[PathBuf]) -> Option<Self> {
+        let path = Self::path(event_file_paths)?;
+        if !path.exists() {
+            return None;
+        }
+
+        let index = match Self::load(&path) {
+            Ok(index) => index,
+            Err(err) => {
+                eprintln!("Warning: ignoring index {}: {err}", path.display());
+                return None;
+            }
+        };
+        if !index.is_current(event_file_paths) {
+            eprintln!(
+                "Warning: ignoring stale index {}. Re-run `index` to update it.",
+                path.display()
+            );
+            return None;
+        }
+        Some(index)
+    }
+
+    /// Whether the index matches the names and sizes of the event files.
+    pub fn is_current(&self, event_file_paths: &[PathBuf]) -> bool {
+        self.files.len() == event_file_paths.len()
+            && self
+                .files
+                .iter()
+                .zip(event_file_paths)
+                .all(|(indexed_file, path)| {
+                    path.file_name() == Some(indexed_file.file_name.as_os_str())
+                        && std::fs::metadata(path)
+                            .is_ok_and(|metadata| metadata.len() == indexed_file.file_size)
+                })
+    }
+
+    /// Offset to start reading each event file from to reach `target`.
+    pub fn start_offsets(&self, target: SeekTarget) -> Vec<u64> {
+        match target {
+            SeekTarget::Slot(slot) => self.slot_offsets(slot),
+            SeekTarget::Timestamp(timestamp) => self.timestamp_offsets(timestamp),
+        }
+    }
+
+    fn slot_offsets(&self, slot: Slot) -> Vec<u64> {
+        let previous_slot_checkpoint = self
+            .files
+            .iter()
+            .flat_map(|file| &file.checkpoints)
+            .filter(|checkpoint| checkpoint.slot.is_some_and(|s| s < slot))
+            .max_by_key(|checkpoint| checkpoint.slot);
+        match previous_slot_checkpoint {
+            Some(checkpoint) => self.timestamp_offsets(checkpoint.timestamp),
+            None => vec![0; self.files.len()],
+        }
+    }
+
+    fn timestamp_offsets(&self, timestamp: SystemTime) -> Vec<u64> {
+        // Timestamps within a file are only roughly ordered, so back off by a
+        // bucket to make sure no events at or after `timestamp` are skipped.
+        let timestamp = timestamp
+            .checked_sub(TIMESTAMP_BUCKET)
+            .unwrap_or(UNIX_EPOCH);
+        self.files
+            .iter()
+            .map(|file| {
+                let end = file
+                    .checkpoints
+                    .partition_point(|checkpoint| checkpoint.timestamp <= timestamp);
+                end.checked_sub(1)
+                    .map(|index| file.checkpoints[index].offset)
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+fn index_event_file(path: &Path) -> std::io::Result<IndexedEventFile> {
+    let mut reader = EventFileReader::open(path)?;
+    let mut checkpoints = Vec::new();
+    let mut current_bucket = None;
+    loop {
+        let offset = reader.offset();
+        let Some(TimedTracedEvent(timestamp, event)) = reader.next_event()? else {
+            break;
+        };
+
+        let slot = match event {
+            TracedEvent::PacketBatch(_, _) => None,
+            TracedEvent::BlockAndBankHash(slot, _, _) => Some(slot),
+        };
+        let bucket = timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            / TIMESTAMP_BUCKET.as_secs();
+        let new_bucket = current_bucket.is_none_or(|current_bucket| bucket > current_bucket);
+        if new_bucket {
+            current_bucket = Some(bucket);
+        }
+        if new_bucket || slot.is_some() {
+            checkpoints.push(Checkpoint {
+                timestamp,
+                offset,
+                slot,
+            });
+        }
+    }
+
+    Ok(IndexedEventFile {
+        file_name: path.file_name().map(PathBuf::from).unwrap_or_default(),
+        file_size: std::fs::metadata(path)?.len(),
+        checkpoints,
+    })
+}
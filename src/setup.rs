@@ -1,48 +1,410 @@
-use std::{
-    fs::File,
-    io::Read,
-    path::{Path, PathBuf},
-    process::exit,
-    time::SystemTime,
+use {
+    flate2::read::MultiGzDecoder,
+    std::{
+        fs::File,
+        io::{BufRead, BufReader, Read, Seek, SeekFrom},
+        path::{Path, PathBuf},
+        time::{SystemTime, UNIX_EPOCH},
+    },
 };
 
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Extensions of individually compressed event files.
+const COMPRESSED_EXTENSIONS: [&str; 2] = ["gz", "zst"];
+
+/// Prefix of the directories archives are extracted to.
+const EXTRACTED_ARCHIVE_PREFIX: &str = "banking-trace-";
+
 /// Get event file paths ordered by first timestamp.
-pub fn get_event_file_paths(path: impl AsRef<Path>) -> Vec<PathBuf> {
-    let mut event_file_paths = get_event_file_paths_unordered(path);
-    event_file_paths.sort_by_key(|event_filepath| {
-        read_first_timestamp(event_filepath).unwrap_or_else(|err| {
-            eprintln!(
-                "Error reading first timestamp from {}: {}",
-                event_filepath.display(),
-                err
-            );
-            exit(1);
+///
+/// `path` is either a trace directory, or a tar archive of one, which may be
+/// gzip or zstd compressed. Event files may also be individually compressed.
+/// The event files in an archive are extracted with [`extract_archive`], and
+/// the paths of the extracted files are returned. An archive must hold a
+/// single trace, so all of its event files must be in the same directory.
+pub fn get_event_file_paths(path: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let event_file_paths = if path.is_file() {
+        let event_file_paths = find_event_files(&extract_archive(path)?)?;
+        check_single_trace(path, &event_file_paths)?;
+        event_file_paths
+    } else {
+        get_event_file_paths_unordered(path)
+    };
+    let mut event_file_paths = event_file_paths
+        .into_iter()
+        .map(|event_filepath| {
+            let first_timestamp = read_first_timestamp(&event_filepath)?;
+            Ok((first_timestamp, event_filepath))
         })
-    });
-    event_file_paths
+        .collect::<std::io::Result<Vec<_>>>()?;
+    event_file_paths.sort_by_key(|(first_timestamp, _)| *first_timestamp);
+    Ok(event_file_paths
+        .into_iter()
+        .map(|(_, event_filepath)| event_filepath)
+        .collect())
 }
 
 fn get_event_file_paths_unordered(path: impl AsRef<Path>) -> Vec<PathBuf> {
     (0..)
-        .map(|index| {
+        .map_while(|index| {
             let event_filename = if index == 0 {
                 "events".to_owned()
             } else {
                 format!("events.{index}")
             };
-            path.as_ref().join(event_filename)
+            let event_filepath = path.as_ref().join(&event_filename);
+            std::iter::once(event_filepath)
+                .chain(
+                    COMPRESSED_EXTENSIONS.iter().map(|extension| {
+                        path.as_ref().join(format!("{event_filename}.{extension}"))
+                    }),
+                )
+                .find(|event_filepath| event_filepath.exists())
         })
-        .take_while(|event_filepath| event_filepath.exists())
         .collect()
 }
 
+/// Whether `file_name` is the name of an event file, such as `events.3` or
+/// `events.3.zst`.
+fn is_event_file_name(file_name: &str) -> bool {
+    let file_name = COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|extension| file_name.strip_suffix(&format!(".{extension}")))
+        .unwrap_or(file_name);
+    match file_name.strip_prefix("events") {
+        Some("") => true,
+        Some(suffix) => suffix
+            .strip_prefix('.')
+            .is_some_and(|index| index.parse::<usize>().is_ok()),
+        None => false,
+    }
+}
+
+/// Find the event files anywhere under `directory`.
+fn find_event_files(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut event_file_paths = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            event_file_paths.extend(find_event_files(&entry.path())?);
+        } else if entry.file_name().to_str().is_some_and(is_event_file_name) {
+            event_file_paths.push(entry.path());
+        }
+    }
+    Ok(event_file_paths)
+}
+
+/// Fail if the event files of the archive at `archive_path` are in more than
+/// one directory, since they would be read as one trace.
+fn check_single_trace(archive_path: &Path, event_file_paths: &[PathBuf]) -> std::io::Result<()> {
+    let mut directories: Vec<_> = event_file_paths
+        .iter()
+        .filter_map(|event_file_path| event_file_path.parent())
+        .collect();
+    directories.sort();
+    directories.dedup();
+    if directories.len() > 1 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} holds event files in {} directories, which are separate traces. Extract it \
+                 and pass each trace directory to --path instead.",
+                archive_path.display(),
+                directories.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Extract the event files in the archive at `path` and return the directory
+/// they were extracted to, keeping their paths within the archive.
+///
+/// Archives are extracted into the system temp directory, so event files can
+/// be decoded in parallel and read from any offset without decompressing the
+/// archive each time. The directory is named after the archive's name, size
+/// and modification time, so later runs on the same archive reuse it. It is
+/// not deleted, so its path is printed when it is created.
+pub fn extract_archive(path: &Path) -> std::io::Result<PathBuf> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let directory = std::env::temp_dir().join(format!(
+        "{EXTRACTED_ARCHIVE_PREFIX}{file_name}-{}-{}",
+        metadata.len(),
+        modified.as_nanos()
+    ));
+    if directory.is_dir() {
+        return Ok(directory);
+    }
+
+    // Extract into a directory of our own, and only move it into place once
+    // complete, so an interrupted extraction is never mistaken for a complete
+    // one.
+    let mut partial_directory = directory.clone().into_os_string();
+    partial_directory.push(format!(".partial-{}", std::process::id()));
+    let partial_directory = PathBuf::from(partial_directory);
+    // Left behind by an interrupted run with the same process id.
+    if partial_directory.exists() {
+        std::fs::remove_dir_all(&partial_directory)?;
+    }
+    let result = unpack_event_files(path, &partial_directory).and_then(|()| {
+        match std::fs::rename(&partial_directory, &directory) {
+            Ok(()) => Ok(()),
+            // Another run extracted the same archive first.
+            Err(_) if directory.is_dir() => std::fs::remove_dir_all(&partial_directory),
+            Err(err) => Err(err),
+        }
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&partial_directory);
+    }
+    result?;
+    eprintln!(
+        "Extracted {} to {}. Delete it once the archive is no longer needed.",
+        path.display(),
+        directory.display()
+    );
+    Ok(directory)
+}
+
+/// Unpack the event files in the archive at `path` into `directory`.
+fn unpack_event_files(path: &Path, directory: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(directory)?;
+    let mut archive = tar::Archive::new(decompress(File::open(path)?)?);
+    // PAX and GNU long names are resolved while reading the entries.
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let is_event_file = entry
+            .path()?
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(is_event_file_name);
+        if is_event_file {
+            entry.unpack_in(directory)?;
+        }
+    }
+    Ok(())
+}
+
+fn skip_bytes(reader: &mut impl Read, num_bytes: u64) -> std::io::Result<()> {
+    let skipped = std::io::copy(&mut reader.take(num_bytes), &mut std::io::sink())?;
+    if skipped < num_bytes {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Wrap `reader` in a gzip or zstd decoder if its contents are compressed.
+fn decompress<'a>(reader: impl Read + Send + 'a) -> std::io::Result<Box<dyn Read + Send + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(MultiGzDecoder::new(reader)))
+    } else if magic.starts_with(&ZSTD_MAGIC) {
+        Ok(Box::new(zstd::Decoder::with_buffer(reader)?))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// An opened event file, positioned at the requested offset.
+pub struct EventFileSource {
+    pub reader: Box<dyn Read + Send>,
+    /// Size of the decompressed event file, if it is known without reading
+    /// the whole file.
+    pub size: Option<u64>,
+}
+
+/// Open the event file at `path`, decompressing it if necessary, and skip to
+/// `offset` in the decompressed data. Only uncompressed files support seeking.
+/// Otherwise, everything before `offset` is decompressed and skipped.
+pub fn open_event_file(path: impl AsRef<Path>, offset: u64) -> std::io::Result<EventFileSource> {
+    let mut file = File::open(path)?;
+    if !is_compressed(&mut file)? {
+        let size = file.metadata()?.len();
+        file.seek(SeekFrom::Start(offset))?;
+        return Ok(EventFileSource {
+            reader: Box::new(file),
+            size: Some(size),
+        });
+    }
+
+    let mut reader = decompress(file)?;
+    skip_bytes(&mut reader, offset)?;
+    Ok(EventFileSource { reader, size: None })
+}
+
+/// Whether the event file at `path` is gzip or zstd compressed.
+pub(crate) fn is_compressed_event_file(path: impl AsRef<Path>) -> std::io::Result<bool> {
+    is_compressed(&mut File::open(path)?)
+}
+
+/// Whether the contents of `file` are gzip or zstd compressed. The file is
+/// rewound afterwards.
+fn is_compressed(file: &mut File) -> std::io::Result<bool> {
+    let mut magic = [0u8; ZSTD_MAGIC.len()];
+    let bytes_read = file.read(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+    let magic = &magic[..bytes_read];
+    Ok(magic.starts_with(&GZIP_MAGIC) || magic.starts_with(&ZSTD_MAGIC))
+}
+
 fn read_first_timestamp(path: impl AsRef<Path>) -> std::io::Result<SystemTime> {
+    let path = path.as_ref();
+    open_event_file(path, 0)
+        .and_then(|source| read_timestamp(source.reader))
+        .map_err(|err| first_timestamp_error(path, err))
+}
+
+fn first_timestamp_error(path: &Path, err: std::io::Error) -> std::io::Error {
+    std::io::Error::new(
+        err.kind(),
+        format!(
+            "Error reading first timestamp from {}: {}",
+            path.display(),
+            err
+        ),
+    )
+}
+
+pub(crate) fn read_timestamp(mut reader: impl Read) -> std::io::Result<SystemTime> {
     const SYSTEM_TIME_BYTES: usize = core::mem::size_of::<SystemTime>();
     let mut buffer = [0u8; SYSTEM_TIME_BYTES];
 
-    let mut file = File::open(path)?;
-    file.read_exact(&mut buffer)?;
+    reader.read_exact(&mut buffer)?;
 
     let system_time = bincode::deserialize(&buffer).unwrap();
     Ok(system_time)
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::time::Duration,
+        tar::{Builder, EntryType, Header},
+    };
+
+    fn append_entry(builder: &mut Builder<File>, path: &str, entry_type: EntryType, data: &[u8]) {
+        let mut header = Header::new_ustar();
+        header.set_entry_type(entry_type);
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, path, data).unwrap();
+    }
+
+    /// A PAX extended header record, prefixed with its own length.
+    fn pax_record(key: &str, value: &str) -> String {
+        let record = format!(" {key}={value}\n");
+        let mut len = record.len();
+        while len.to_string().len() + record.len() != len {
+            len = len.to_string().len() + record.len();
+        }
+        format!("{len}{record}")
+    }
+
+    /// The start of a record: its timestamp and the variant of its event.
+    fn record_header(secs: u64) -> Vec<u8> {
+        let mut record_header =
+            bincode::serialize(&(UNIX_EPOCH + Duration::from_secs(secs))).unwrap();
+        record_header.extend(0u32.to_le_bytes());
+        record_header
+    }
+
+    #[test]
+    fn test_get_event_file_paths_from_archive() {
+        let directory = std::env::temp_dir().join(format!("setup-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let archive_path = directory.join("trace.tar");
+        // Long enough to need GNU long name entries.
+        let trace_directory = format!("trace/{}", "d".repeat(120));
+
+        let mut builder = Builder::new(File::create(&archive_path).unwrap());
+        append_entry(
+            &mut builder,
+            &format!("{trace_directory}/events.2"),
+            EntryType::Directory,
+            &[],
+        );
+        append_entry(
+            &mut builder,
+            &format!("{trace_directory}/events"),
+            EntryType::Regular,
+            &record_header(3),
+        );
+        append_entry(
+            &mut builder,
+            &format!("{trace_directory}/events.1"),
+            EntryType::Regular,
+            &record_header(1),
+        );
+        // A PAX header named like bsdtar does, renaming the next entry.
+        append_entry(
+            &mut builder,
+            "PaxHeader/events",
+            EntryType::XHeader,
+            pax_record("path", &format!("{trace_directory}/events.3")).as_bytes(),
+        );
+        append_entry(
+            &mut builder,
+            "trace/short",
+            EntryType::Regular,
+            &record_header(2),
+        );
+        append_entry(&mut builder, "trace/notes", EntryType::Regular, b"notes");
+        builder.into_inner().unwrap();
+
+        let event_file_paths = get_event_file_paths(&archive_path).unwrap();
+        let extracted_directory = extract_archive(&archive_path).unwrap();
+        assert_eq!(
+            event_file_paths,
+            vec![
+                extracted_directory.join(format!("{trace_directory}/events.1")),
+                extracted_directory.join(format!("{trace_directory}/events.3")),
+                extracted_directory.join(format!("{trace_directory}/events")),
+            ]
+        );
+
+        std::fs::remove_dir_all(extracted_directory).unwrap();
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_get_event_file_paths_rejects_archive_of_several_traces() {
+        let directory =
+            std::env::temp_dir().join(format!("setup-several-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let archive_path = directory.join("traces.tar");
+
+        let mut builder = Builder::new(File::create(&archive_path).unwrap());
+        append_entry(
+            &mut builder,
+            "a/events",
+            EntryType::Regular,
+            &record_header(1),
+        );
+        append_entry(
+            &mut builder,
+            "b/events",
+            EntryType::Regular,
+            &record_header(2),
+        );
+        builder.into_inner().unwrap();
+
+        let err = get_event_file_paths(&archive_path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        std::fs::remove_dir_all(extract_archive(&archive_path).unwrap()).unwrap();
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use {
+    crate::{
+        cost_model::{transaction_cost, MAX_VOTE_UNITS, SIMPLE_VOTE_USAGE_COST},
+        fee::{transaction_fee, TransactionFee},
+        slot_packets::{read_slot_packets, SlotPacketsHandler},
+    },
+    solana_address::Address,
+    solana_clock::Slot,
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        collections::{HashMap, HashSet},
+        path::PathBuf,
+    },
+};
+
+/// Limits the simulated block is packed under, in compute units.
+pub struct BlockLimits {
+    pub block_cu_limit: u64,
+    pub account_cu_limit: u64,
+    /// CUs of the block reserved for votes. If not set, the cost of the
+    /// unique votes received during the slot is reserved, up to
+    /// `MAX_VOTE_UNITS`.
+    pub vote_reservation: Option<u64>,
+}
+
+/// Greedily pack the non-vote transactions received during `slot` into a
+/// block, highest priority first, and report what made it in and which high
+/// priority transactions were left out because of account contention.
+pub fn simulate_block(
+    event_file_paths: &[PathBuf],
+    slot: Slot,
+    limits: BlockLimits,
+    skipped_count: usize,
+) -> std::io::Result<()> {
+    let slot_packets = read_slot_packets(event_file_paths, slot)?;
+    report(&slot_packets, limits, skipped_count);
+    Ok(())
+}
+
+/// A transaction considered for the block.
+struct Candidate {
+    signature: Signature,
+    priority: u64,
+    cost: u64,
+    fee: TransactionFee,
+    writable_accounts: Vec<Address>,
+}
+
+/// Pack the transactions of the slot and report the simulated block:
+/// - Total CUs, fees and transaction count
+/// - Transactions left out for the block limit or an account limit
+/// - The highest priority transactions left out for an account limit
+fn report(slot_packets: &SlotPacketsHandler, limits: BlockLimits, skipped_count: usize) {
+    let slot_transactions = slot_packets.unique_transactions();
+    let mut candidates = Vec::new();
+    for (tx, compute_budget_limits) in &slot_transactions.transactions {
+        candidates.push(Candidate {
+            signature: *tx.signature(),
+            priority: compute_budget_limits.compute_unit_price,
+            cost: transaction_cost(tx, compute_budget_limits).sum(),
+            fee: transaction_fee(
+                tx.signatures().len(),
+                tx.message().program_instructions_iter(),
+                compute_budget_limits,
+            ),
+            writable_accounts: tx
+                .get_account_locks_unchecked()
+                .writable
+                .into_iter()
+                .copied()
+                .collect(),
+        });
+    }
+    // Stable sort, so equal priorities are packed in arrival order.
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.priority));
+
+    // Votes are counted once, whichever channel they arrived on.
+    let vote_signatures: HashSet<_> = slot_packets
+        .vote_packet_batches()
+        .iter()
+        .flat_map(|b| b.iter().flat_map(|b| b.iter()))
+        .filter_map(|packet| bincode::deserialize::<VersionedTransaction>(packet.data(..)?).ok())
+        .filter_map(|transaction| transaction.signatures.first().copied())
+        .collect();
+    let vote_reservation = limits.vote_reservation.unwrap_or_else(|| {
+        (vote_signatures.len() as u64 * SIMPLE_VOTE_USAGE_COST).min(MAX_VOTE_UNITS)
+    });
+    let non_vote_limit = limits.block_cu_limit.saturating_sub(vote_reservation);
+
+    let mut block_cus = 0;
+    let mut account_cus: HashMap<Address, u64> = HashMap::new();
+    let mut packed = 0;
+    let mut base_fees = 0;
+    let mut prioritization_fees = 0;
+    let mut block_limited = 0;
+    // Left out candidates, with the account whose limit they would exceed.
+    let mut account_limited = Vec::new();
+    for candidate in &candidates {
+        if block_cus + candidate.cost > non_vote_limit {
+            block_limited += 1;
+            continue;
+        }
+        if let Some(account) = candidate.writable_accounts.iter().find(|account| {
+            account_cus.get(account).copied().unwrap_or_default() + candidate.cost
+                > limits.account_cu_limit
+        }) {
+            account_limited.push((candidate, account));
+            continue;
+        }
+
+        block_cus += candidate.cost;
+        for account in &candidate.writable_accounts {
+            *account_cus.entry(*account).or_default() += candidate.cost;
+        }
+        packed += 1;
+        base_fees += candidate.fee.base_fee;
+        prioritization_fees += candidate.fee.prioritization_fee;
+    }
+
+    println!(
+        "Slot {}: {} unique transactions",
+        slot_packets.slot(),
+        candidates.len()
+    );
+    println!(
+        "Vote reservation: {vote_reservation} CUs ({} unique votes received)",
+        vote_signatures.len()
+    );
+    println!("Packed transactions: {packed}");
+    println!("Block CUs: {block_cus} of {non_vote_limit}");
+    println!(
+        "Fees: total={} base={base_fees} prioritization={prioritization_fees}",
+        base_fees + prioritization_fees
+    );
+    println!(
+        "Left out: block_limit={block_limited} account_limit={}",
+        account_limited.len()
+    );
+    println!("Top {skipped_count} transactions left out by account limits:");
+    for (candidate, account) in account_limited.into_iter().take(skipped_count) {
+        println!(
+            "  {}: priority={} cus={} account={account} account_cus={}",
+            candidate.signature,
+            candidate.priority,
+            candidate.cost,
+            account_cus.get(account).copied().unwrap_or_default(),
+        );
+    }
+
+    slot_transactions.report_decode_failures();
+}
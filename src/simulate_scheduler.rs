@@ -0,0 +1,190 @@
+use {
+    crate::{
+        graphia_input::PriorityIndex,
+        slot_packets::{read_slot_packets, SlotTransactions},
+    },
+    prio_graph::{AccessKind, PrioGraph},
+    solana_clock::Slot,
+    std::{
+        cmp::Reverse,
+        collections::{BTreeMap, BinaryHeap},
+        path::PathBuf,
+    },
+};
+
+/// Compute units executed per microsecond, used to show simulated times.
+const COMPUTE_UNITS_PER_US: u64 = 30;
+/// Width of the widest bar in the parallelism histogram.
+const HISTOGRAM_WIDTH: usize = 50;
+
+/// Schedule the non-vote transactions received during `slot` onto
+/// `num_threads` simulated execution threads, driving a `PrioGraph` the way
+/// the banking stage scheduler does.
+///
+/// Whenever threads are idle, every transaction the graph has unblocked is
+/// ready, and each idle thread takes a batch of up to `batch_size` of the
+/// highest priority ready transactions. A batch runs for the sum of its
+/// transactions' requested CUs, and only unblocks conflicting transactions
+/// once it completes. Times are measured in CUs.
+pub fn simulate_scheduler(
+    event_file_paths: &[PathBuf],
+    slot: Slot,
+    num_threads: usize,
+    batch_size: usize,
+) -> std::io::Result<()> {
+    let slot_packets = read_slot_packets(event_file_paths, slot)?;
+    report(
+        slot,
+        slot_packets.unique_transactions(),
+        num_threads,
+        batch_size,
+    );
+    Ok(())
+}
+
+/// A batch of transactions running on a simulated thread.
+struct RunningBatch {
+    end: u64,
+    ids: Vec<PriorityIndex>,
+}
+
+/// Run the simulation and report:
+/// - Makespan, total work, and the speedup over a single thread
+/// - Utilization of each thread
+/// - Histogram of the number of ready transactions at each step
+fn report(
+    slot: Slot,
+    mut slot_transactions: SlotTransactions,
+    num_threads: usize,
+    batch_size: usize,
+) {
+    let transactions = &mut slot_transactions.transactions;
+    // Stable sort, so equal priorities are inserted in arrival order.
+    transactions.sort_by_key(|(_, compute_budget_limits)| {
+        Reverse(compute_budget_limits.compute_unit_price)
+    });
+
+    // Insert into prio-graph in order of priority.
+    let mut prio_graph = PrioGraph::new(|pi, _| *pi);
+    for (index, (transaction, compute_budget_limits)) in transactions.iter().enumerate() {
+        let account_locks = transaction.get_account_locks_unchecked();
+        let write_locks = account_locks
+            .writable
+            .iter()
+            .map(|a| (*a, AccessKind::Write));
+        let read_locks = account_locks
+            .readonly
+            .iter()
+            .map(|a| (*a, AccessKind::Read));
+        prio_graph.insert_transaction(
+            PriorityIndex {
+                priority: compute_budget_limits.compute_unit_price,
+                index,
+            },
+            write_locks.chain(read_locks),
+        );
+    }
+    let cost = |id: &PriorityIndex| u64::from(transactions[id.index].1.compute_unit_limit);
+
+    let mut now = 0;
+    let mut running: Vec<Option<RunningBatch>> = (0..num_threads).map(|_| None).collect();
+    let mut busy_time = vec![0u64; num_threads];
+    // Ready transactions, highest priority first, then in insertion order.
+    let mut ready = BinaryHeap::new();
+    // Number of steps with each number of ready transactions.
+    let mut parallelism: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut num_batches = 0;
+    loop {
+        while let Some(id) = prio_graph.pop() {
+            ready.push((id.priority, Reverse(id.index), id));
+        }
+        if !ready.is_empty() && running.iter().any(Option::is_none) {
+            *parallelism.entry(ready.len()).or_default() += 1;
+        }
+
+        for (thread, batch) in running.iter_mut().enumerate() {
+            if batch.is_some() || ready.is_empty() {
+                continue;
+            }
+            let ids: Vec<_> = std::iter::from_fn(|| ready.pop().map(|(_, _, id)| id))
+                .take(batch_size)
+                .collect();
+            let duration: u64 = ids.iter().map(cost).sum();
+            busy_time[thread] += duration;
+            num_batches += 1;
+            *batch = Some(RunningBatch {
+                end: now + duration,
+                ids,
+            });
+        }
+
+        // Advance to the next batch completion, and unblock its
+        // transactions.
+        let Some(next_end) = running.iter().flatten().map(|batch| batch.end).min() else {
+            break;
+        };
+        now = next_end;
+        for batch in running.iter_mut() {
+            if batch.as_ref().is_some_and(|batch| batch.end == now) {
+                for id in batch.take().unwrap().ids {
+                    prio_graph.unblock(&id);
+                }
+            }
+        }
+    }
+
+    let total_work: u64 = busy_time.iter().sum();
+    let format_cus = |cus: u64| {
+        format!(
+            "{cus} CUs ({:.1}ms)",
+            cus as f64 / COMPUTE_UNITS_PER_US as f64 / 1000.0
+        )
+    };
+    println!(
+        "Slot {}: {} unique transactions in {num_batches} batches on {num_threads} threads \
+             of up to {batch_size}",
+        slot,
+        transactions.len()
+    );
+    println!("Makespan: {}", format_cus(now));
+    println!("Total work: {}", format_cus(total_work));
+    if now > 0 {
+        println!(
+            "Speedup over one thread: {:.2}",
+            total_work as f64 / now as f64
+        );
+    }
+    println!("Thread utilization:");
+    for (thread, busy_time) in busy_time.iter().enumerate() {
+        println!(
+            "  {thread}: {:.1}%",
+            *busy_time as f64 * 100.0 / now.max(1) as f64
+        );
+    }
+
+    println!("Ready transactions per scheduling step:");
+    print_parallelism_histogram(&parallelism);
+
+    slot_transactions.report_decode_failures();
+}
+
+/// Print the number of steps with each number of ready transactions, in
+/// power-of-two buckets.
+fn print_parallelism_histogram(parallelism: &BTreeMap<usize, usize>) {
+    let mut buckets: BTreeMap<usize, usize> = BTreeMap::new();
+    for (num_ready, steps) in parallelism {
+        *buckets.entry(num_ready.next_power_of_two()).or_default() += steps;
+    }
+
+    let max_steps = buckets.values().copied().max().unwrap_or(0).max(1);
+    for (upper, steps) in buckets {
+        let lower = upper / 2 + 1;
+        let label = if lower >= upper {
+            format!("{upper}")
+        } else {
+            format!("{lower}-{upper}")
+        };
+        let bar = "#".repeat(steps * HISTOGRAM_WIDTH / max_steps);
+        println!("  {label:<12} {steps:>8} {bar}");
+    }
+}
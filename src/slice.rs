@@ -0,0 +1,300 @@
+use {
+    crate::filter::PacketFilter,
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{
+        process::{process_event_files, process_event_files_from},
+        seek_index::SeekTarget,
+        TraceWriter,
+    },
+    solana_address::Address,
+    solana_clock::Slot,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_perf::packet::{BytesPacketBatch, PacketBatch},
+    std::{
+        collections::HashSet, net::IpAddr, ops::ControlFlow, path::PathBuf, sync::Arc,
+        time::SystemTime,
+    },
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn slice(
+    event_file_paths: &[PathBuf],
+    output: PathBuf,
+    start_slot: Option<Slot>,
+    end_slot: Option<Slot>,
+    accounts: Option<HashSet<Address>>,
+    ips: Option<HashSet<IpAddr>>,
+    skip_alt_resolution: bool,
+    max_file_size: u64,
+) -> std::io::Result<()> {
+    let writer = TraceWriter::create(&output, max_file_size)?;
+    // ALTs are only needed to match accounts.
+    let skip_alt_resolution = skip_alt_resolution || accounts.is_none();
+    let filter = PacketFilter::new(accounts, ips, skip_alt_resolution);
+    let mut handler = Slicer::new(writer, filter, start_slot, end_slot);
+    let mut handler_fn = |event| handler.handle_event(event);
+    match start_slot {
+        Some(start_slot) => process_event_files_from(
+            event_file_paths,
+            SeekTarget::Slot(start_slot),
+            &mut handler_fn,
+        )?,
+        None => process_event_files(event_file_paths, &mut handler_fn)?,
+    }
+    handler.finish(output)
+}
+
+struct Slicer {
+    writer: TraceWriter,
+    filter: PacketFilter,
+    start_slot: Option<Slot>,
+    end_slot: Option<Slot>,
+    /// Packet batches since the last `BlockAndBankHash`, which belong to the
+    /// slot of the next one. Only buffered when slicing a slot range.
+    pending_packet_batches: Vec<(SystemTime, ChannelLabel, BankingPacketBatch)>,
+    last_slot: Option<Slot>,
+    /// First write error, which stops processing.
+    error: Option<std::io::Error>,
+
+    num_events: usize,
+    num_packets: usize,
+}
+
+impl Slicer {
+    pub fn new(
+        writer: TraceWriter,
+        filter: PacketFilter,
+        start_slot: Option<Slot>,
+        end_slot: Option<Slot>,
+    ) -> Self {
+        Self {
+            writer,
+            filter,
+            start_slot,
+            end_slot,
+            pending_packet_batches: Vec::new(),
+            last_slot: None,
+            error: None,
+            num_events: 0,
+            num_packets: 0,
+        }
+    }
+
+    /// Packets belong to the slot of the next `BlockAndBankHash`, so they are
+    /// buffered until it is seen, and then written along with it if the slot
+    /// is in range.
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                if self.start_slot.is_none() && self.end_slot.is_none() {
+                    return self.write_packet_batches(timestamp, label, packet_batches);
+                }
+                self.pending_packet_batches
+                    .push((timestamp, label, packet_batches));
+                ControlFlow::Continue(())
+            }
+            TracedEvent::BlockAndBankHash(slot, blockhash, bank_hash) => {
+                let pending_packet_batches = std::mem::take(&mut self.pending_packet_batches);
+                self.last_slot = Some(slot);
+                if self.end_slot.is_some_and(|end_slot| slot > end_slot) {
+                    return ControlFlow::Break(());
+                }
+                if self.start_slot.is_some_and(|start_slot| slot < start_slot) {
+                    return ControlFlow::Continue(());
+                }
+
+                for (timestamp, label, packet_batches) in pending_packet_batches {
+                    self.write_packet_batches(timestamp, label, packet_batches)?;
+                }
+                self.write_event(
+                    timestamp,
+                    TracedEvent::BlockAndBankHash(slot, blockhash, bank_hash),
+                )?;
+                if self.end_slot == Some(slot) {
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    fn write_packet_batches(
+        &mut self,
+        timestamp: SystemTime,
+        label: ChannelLabel,
+        packet_batches: BankingPacketBatch,
+    ) -> ControlFlow<()> {
+        match self.filter_packet_batches(label, packet_batches) {
+            Some(packet_batches) => {
+                self.write_event(timestamp, TracedEvent::PacketBatch(label, packet_batches))
+            }
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    /// Keep only the packets that pass the filter. Returns `None` if no
+    /// packets are left.
+    fn filter_packet_batches(
+        &self,
+        label: ChannelLabel,
+        packet_batches: BankingPacketBatch,
+    ) -> Option<BankingPacketBatch> {
+        if self.filter.is_empty() || matches!(label, ChannelLabel::Dummy) {
+            return Some(packet_batches);
+        }
+
+        let filtered_packet_batches: Vec<_> = packet_batches
+            .iter()
+            .filter_map(|packet_batch| {
+                let mut filtered_packet_batch = BytesPacketBatch::with_capacity(packet_batch.len());
+                let mut is_empty = true;
+                for packet in packet_batch
+                    .iter()
+                    .filter(|packet| self.filter.keeps(*packet))
+                {
+                    filtered_packet_batch.push(packet.to_bytes_packet());
+                    is_empty = false;
+                }
+                (!is_empty).then(|| PacketBatch::from(filtered_packet_batch))
+            })
+            .collect();
+        (!filtered_packet_batches.is_empty()).then(|| Arc::new(filtered_packet_batches))
+    }
+
+    fn write_event(&mut self, timestamp: SystemTime, event: TracedEvent) -> ControlFlow<()> {
+        if let TracedEvent::PacketBatch(_, packet_batches) = &event {
+            self.num_packets += packet_batches.iter().map(|b| b.len()).sum::<usize>();
+        }
+        match self.writer.write_event(&TimedTracedEvent(timestamp, event)) {
+            Ok(()) => {
+                self.num_events += 1;
+                ControlFlow::Continue(())
+            }
+            Err(err) => {
+                self.error = Some(err);
+                ControlFlow::Break(())
+            }
+        }
+    }
+
+    pub fn finish(mut self, output: PathBuf) -> std::io::Result<()> {
+        // Packets after the last `BlockAndBankHash` belong to a later slot, so
+        // they are only kept if any later slot is in range.
+        let keep_trailing_packets = self.end_slot.is_none()
+            && self.start_slot.is_none_or(|start_slot| {
+                self.last_slot
+                    .is_some_and(|last_slot| last_slot + 1 >= start_slot)
+            });
+        if keep_trailing_packets {
+            for (timestamp, label, packet_batches) in
+                std::mem::take(&mut self.pending_packet_batches)
+            {
+                if self
+                    .write_packet_batches(timestamp, label, packet_batches)
+                    .is_break()
+                {
+                    break;
+                }
+            }
+        }
+
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        self.writer.finish()?;
+        println!(
+            "Wrote {} events ({} packets) to {}",
+            self.num_events,
+            self.num_packets,
+            output.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        banking_trace_tool::{trace_writer::DEFAULT_MAX_EVENT_FILE_SIZE, TraceReader},
+        solana_hash::Hash,
+        solana_perf::packet::BytesPacket,
+        std::time::{Duration, UNIX_EPOCH},
+    };
+
+    #[test]
+    fn test_slice_at_slot_boundary() {
+        let directory = std::env::temp_dir().join(format!("slice-test-{}", std::process::id()));
+        let writer = TraceWriter::create(&directory, DEFAULT_MAX_EVENT_FILE_SIZE).unwrap();
+        let filter = PacketFilter::new(None, None, true);
+        let mut slicer = Slicer::new(writer, filter, Some(11), Some(11));
+
+        // Packets at each second, followed by the `BlockAndBankHash` of the
+        // slot they belong to.
+        let events = [
+            (1, None),
+            (2, Some(10)),
+            (3, None),
+            (4, Some(11)),
+            (5, None),
+            (6, Some(12)),
+        ];
+        for (seconds, slot) in events {
+            let event = match slot {
+                Some(slot) => TracedEvent::BlockAndBankHash(slot, Hash::default(), Hash::default()),
+                None => TracedEvent::PacketBatch(ChannelLabel::NonVote, Arc::new(Vec::new())),
+            };
+            let timestamp = UNIX_EPOCH + Duration::from_secs(seconds);
+            if slicer
+                .handle_event(TimedTracedEvent(timestamp, event))
+                .is_break()
+            {
+                break;
+            }
+        }
+        slicer.finish(directory.clone()).unwrap();
+
+        let seconds: Vec<_> = TraceReader::open(&directory)
+            .unwrap()
+            .map(|event| {
+                let TimedTracedEvent(timestamp, _) = event.unwrap();
+                timestamp.duration_since(UNIX_EPOCH).unwrap().as_secs()
+            })
+            .collect();
+        assert_eq!(seconds, [3, 4]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_filter_by_ip_keeps_undecodable_packets() {
+        let directory = std::env::temp_dir().join(format!("slice-ip-test-{}", std::process::id()));
+        let writer = TraceWriter::create(&directory, DEFAULT_MAX_EVENT_FILE_SIZE).unwrap();
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let filter = PacketFilter::new(None, Some(HashSet::from([ip])), true);
+        let slicer = Slicer::new(writer, filter, None, None);
+
+        // Payloads too short to hold a transaction, from the kept IP and another.
+        let mut packet_batch = BytesPacketBatch::with_capacity(2);
+        for addr in [ip, IpAddr::from([10, 0, 0, 2])] {
+            let mut packet = BytesPacket::from_data(None, [0u8; 3]).unwrap();
+            packet.meta_mut().addr = addr;
+            packet_batch.push(packet);
+        }
+        let packet_batches = Arc::new(vec![PacketBatch::from(packet_batch)]);
+
+        let filtered_packet_batches = slicer
+            .filter_packet_batches(ChannelLabel::NonVote, packet_batches)
+            .unwrap();
+        let addrs: Vec<_> = filtered_packet_batches
+            .iter()
+            .flat_map(|packet_batch| packet_batch.iter().map(|packet| packet.meta().addr))
+            .collect();
+        assert_eq!(addrs, [ip]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
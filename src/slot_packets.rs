@@ -0,0 +1,177 @@
+use {
+    crate::{
+        cli::SlotRange,
+        compute_budget::ComputeBudgetLimits,
+        decode::{decode_and_resolve, DecodeFailureCounts, REPORTED_IP_COUNT},
+    },
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{process::process_event_files_from, seek_index::SeekTarget},
+    solana_alt_store::Store,
+    solana_clock::Slot,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_transaction::sanitized::SanitizedTransaction,
+    std::{cmp::Ordering, collections::HashSet, ops::ControlFlow, path::PathBuf},
+};
+
+/// Read the packets received during `slot`: those after the `BlockAndBankHash`
+/// of the previous slot, up to the `BlockAndBankHash` of `slot`.
+pub fn read_slot_packets(
+    event_file_paths: &[PathBuf],
+    slot: Slot,
+) -> std::io::Result<SlotPacketsHandler> {
+    let mut handler = SlotPacketsHandler::new(slot);
+    process_event_files_from(event_file_paths, SeekTarget::Slot(slot), &mut |event| {
+        handler.handle_event(event)
+    })?;
+    if !handler.found {
+        return Err(std::io::Error::other(format!(
+            "slot {slot} not found in trace"
+        )));
+    }
+    Ok(handler)
+}
+
+/// Pass the non-vote packet batches of each slot in `slot_range` to
+/// `handle_slot`, once the `BlockAndBankHash` of the slot is seen.
+pub fn process_slot_range_packets(
+    event_file_paths: &[PathBuf],
+    slot_range: SlotRange,
+    handle_slot: &mut impl FnMut(Slot, Vec<BankingPacketBatch>),
+) -> std::io::Result<()> {
+    let range = slot_range.start_slot..=slot_range.end_slot;
+    let mut non_vote_packet_batches = Vec::new();
+    process_event_files_from(
+        event_file_paths,
+        SeekTarget::Slot(slot_range.start_slot),
+        &mut |TimedTracedEvent(_timestamp, event)| {
+            match event {
+                TracedEvent::PacketBatch(ChannelLabel::NonVote, packet_batches) => {
+                    non_vote_packet_batches.push(packet_batches)
+                }
+                TracedEvent::PacketBatch(_, _) => {}
+                TracedEvent::BlockAndBankHash(slot, _, _) => {
+                    if slot > *range.end() {
+                        return ControlFlow::Break(());
+                    }
+                    let packet_batches = std::mem::take(&mut non_vote_packet_batches);
+                    if range.contains(&slot) {
+                        handle_slot(slot, packet_batches);
+                    }
+                }
+            }
+            ControlFlow::Continue(())
+        },
+    )
+}
+
+/// Buffers the packet batches of a single slot.
+pub struct SlotPacketsHandler {
+    slot: Slot,
+    non_vote_packet_batches: Vec<BankingPacketBatch>,
+    vote_packet_batches: Vec<BankingPacketBatch>,
+    alt_store: Store,
+    /// Whether the `BlockAndBankHash` of `slot` was seen.
+    found: bool,
+}
+
+/// The decoded non-vote transactions of a slot, in arrival order.
+pub struct SlotTransactions {
+    pub transactions: Vec<(SanitizedTransaction, ComputeBudgetLimits)>,
+    pub decode_failures: DecodeFailureCounts,
+}
+
+impl SlotPacketsHandler {
+    pub fn new(slot: Slot) -> Self {
+        const ALT_STORE_PATH: &str = "alt-store.bin";
+
+        Self {
+            slot,
+            non_vote_packet_batches: Vec::new(),
+            vote_packet_batches: Vec::new(),
+            alt_store: Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store"),
+            found: false,
+        }
+    }
+
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// Packet batches received on the TPU and gossip vote channels.
+    pub fn vote_packet_batches(&self) -> &[BankingPacketBatch] {
+        &self.vote_packet_batches
+    }
+
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                match label {
+                    ChannelLabel::NonVote => self.non_vote_packet_batches.push(packet_batches),
+                    ChannelLabel::TpuVote | ChannelLabel::GossipVote => {
+                        self.vote_packet_batches.push(packet_batches)
+                    }
+                    ChannelLabel::Dummy => {}
+                }
+                ControlFlow::Continue(())
+            }
+            TracedEvent::BlockAndBankHash(slot, _, _) => self.handle_block_and_bank_hash(slot),
+        }
+    }
+
+    fn handle_block_and_bank_hash(&mut self, slot: Slot) -> ControlFlow<()> {
+        match slot.cmp(&self.slot) {
+            Ordering::Less => {
+                self.non_vote_packet_batches.clear();
+                self.vote_packet_batches.clear();
+                ControlFlow::Continue(())
+            }
+            Ordering::Equal => {
+                self.found = true;
+                ControlFlow::Break(())
+            }
+            // The slot is not in the trace.
+            Ordering::Greater => ControlFlow::Break(()),
+        }
+    }
+
+    /// Decode and resolve the non-vote transactions, keeping every copy of
+    /// those received more than once.
+    pub fn transactions(&self) -> SlotTransactions {
+        let mut decode_failures = DecodeFailureCounts::default();
+        let mut transactions = Vec::new();
+        for packet in self
+            .non_vote_packet_batches
+            .iter()
+            .flat_map(|b| b.iter().flat_map(|b| b.iter()))
+        {
+            match decode_and_resolve(packet, &self.alt_store) {
+                Ok(transaction) => transactions.push(transaction),
+                Err(failure) => decode_failures.record(packet.meta().addr, failure),
+            }
+        }
+        SlotTransactions {
+            transactions,
+            decode_failures,
+        }
+    }
+
+    /// Like `transactions`, but only the first copy of each signature is kept,
+    /// as a block can only include a transaction once.
+    pub fn unique_transactions(&self) -> SlotTransactions {
+        let mut slot_transactions = self.transactions();
+        let mut signatures = HashSet::new();
+        slot_transactions
+            .transactions
+            .retain(|(tx, _)| signatures.insert(*tx.signature()));
+        slot_transactions
+    }
+}
+
+impl SlotTransactions {
+    pub fn report_decode_failures(&self) {
+        self.decode_failures.report(REPORTED_IP_COUNT);
+    }
+}
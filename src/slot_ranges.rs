@@ -1,8 +1,8 @@
 use {
-    crate::process::process_event_files,
+    banking_trace_tool::process::process_event_files,
     solana_clock::Slot,
     solana_core::banking_trace::{TimedTracedEvent, TracedEvent},
-    std::path::PathBuf,
+    std::{ops::ControlFlow, path::PathBuf},
 };
 
 pub fn slot_ranges(event_file_paths: &[PathBuf]) -> std::io::Result<()> {
@@ -18,7 +18,10 @@ struct SlotRangesHandler {
 }
 
 impl SlotRangesHandler {
-    pub fn handle_event(&mut self, TimedTracedEvent(_timestamp, event): TimedTracedEvent) {
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
         if let TracedEvent::BlockAndBankHash(slot, _, _) = event {
             match &mut self.current_range {
                 Some((_start_slot, end_slot)) => {
@@ -32,6 +35,7 @@ impl SlotRangesHandler {
                 None => self.current_range = Some((slot, slot)),
             }
         }
+        ControlFlow::Continue(())
     }
 
     fn report_current_range(&self) {
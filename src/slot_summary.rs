@@ -0,0 +1,222 @@
+use {
+    crate::{compute_budget::compute_budget_limits, fee::transaction_fee},
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::process::process_event_files,
+    chrono::{DateTime, Utc},
+    solana_clock::Slot,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_hash::Hash,
+    solana_signature::Signature,
+    solana_transaction::versioned::{
+        sanitized::SanitizedVersionedTransaction, VersionedTransaction,
+    },
+    std::{collections::HashSet, ops::ControlFlow, path::PathBuf, time::SystemTime},
+};
+
+/// Print one row per `BlockAndBankHash` in the trace, as a table or as CSV.
+///
+/// The marker for a slot is recorded once the slot's bank is complete, when
+/// the next leader slot starts. So each row counts the events after the
+/// previous marker, up to and including the slot's own marker.
+pub fn slot_summary(event_file_paths: &[PathBuf], csv: bool) -> std::io::Result<()> {
+    let mut handler = SlotSummaryHandler::new(csv);
+    handler.print_header();
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    handler.report_trailing_events();
+    Ok(())
+}
+
+struct SlotSummaryHandler {
+    csv: bool,
+    current_slot: SlotCounts,
+}
+
+#[derive(Default)]
+struct SlotCounts {
+    /// Timestamp of the first event after the previous marker.
+    start: Option<SystemTime>,
+
+    non_vote: ChannelCounts,
+    tpu_vote: ChannelCounts,
+    gossip_vote: ChannelCounts,
+
+    valid_packets: usize,
+    staked_packets: usize,
+    signatures: HashSet<Signature>,
+    /// Total fee of the unique non-vote transactions, in lamports.
+    fees_offered: u64,
+}
+
+#[derive(Default)]
+struct ChannelCounts {
+    packets: usize,
+    batches: usize,
+}
+
+impl SlotSummaryHandler {
+    pub fn new(csv: bool) -> Self {
+        Self {
+            csv,
+            current_slot: SlotCounts::default(),
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        self.current_slot.start.get_or_insert(timestamp);
+        match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                self.handle_packet_batches(label, packet_batches)
+            }
+            TracedEvent::BlockAndBankHash(slot, blockhash, bank_hash) => {
+                self.handle_block_and_bank_hash(timestamp, slot, blockhash, bank_hash)
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn handle_packet_batches(&mut self, label: ChannelLabel, packet_batches: BankingPacketBatch) {
+        let counts = &mut self.current_slot;
+        let channel_counts = match label {
+            ChannelLabel::NonVote => &mut counts.non_vote,
+            ChannelLabel::TpuVote => &mut counts.tpu_vote,
+            ChannelLabel::GossipVote => &mut counts.gossip_vote,
+            ChannelLabel::Dummy => return,
+        };
+
+        for packet_batch in packet_batches.iter() {
+            channel_counts.batches += 1;
+            for packet in packet_batch {
+                channel_counts.packets += 1;
+                if packet.meta().discard() {
+                    continue;
+                }
+                counts.valid_packets += 1;
+                counts.staked_packets += usize::from(packet.meta().is_from_staked_node());
+
+                let Some(transaction) = packet
+                    .data(..)
+                    .and_then(|data| bincode::deserialize::<VersionedTransaction>(data).ok())
+                else {
+                    continue;
+                };
+                let Some(signature) = transaction.signatures.first().copied() else {
+                    continue;
+                };
+                if counts.signatures.insert(signature) && matches!(label, ChannelLabel::NonVote) {
+                    counts.fees_offered += offered_fee(transaction);
+                }
+            }
+        }
+    }
+
+    fn handle_block_and_bank_hash(
+        &mut self,
+        timestamp: SystemTime,
+        slot: Slot,
+        blockhash: Hash,
+        bank_hash: Hash,
+    ) {
+        let counts = std::mem::take(&mut self.current_slot);
+        let start = format_timestamp(counts.start.unwrap_or(timestamp));
+        let end = format_timestamp(timestamp);
+        let SlotCounts {
+            start: _,
+            non_vote,
+            tpu_vote,
+            gossip_vote,
+            valid_packets,
+            staked_packets,
+            signatures,
+            fees_offered,
+        } = counts;
+        let unique_signatures = signatures.len();
+
+        if self.csv {
+            println!(
+                "{slot},{blockhash},{bank_hash},{start},{end},{},{},{},{},{},{},{valid_packets},\
+                 {staked_packets},{unique_signatures},{fees_offered}",
+                non_vote.packets,
+                non_vote.batches,
+                tpu_vote.packets,
+                tpu_vote.batches,
+                gossip_vote.packets,
+                gossip_vote.batches,
+            );
+        } else {
+            println!(
+                "{slot:>12} {blockhash:>44} {bank_hash:>44} {start:>27} {end:>27} {:>9} {:>8} \
+                 {:>9} {:>8} {:>9} {:>8} {valid_packets:>9} {staked_packets:>9} \
+                 {unique_signatures:>9} {fees_offered:>14}",
+                non_vote.packets,
+                non_vote.batches,
+                tpu_vote.packets,
+                tpu_vote.batches,
+                gossip_vote.packets,
+                gossip_vote.batches,
+            );
+        }
+    }
+
+    fn print_header(&self) {
+        if self.csv {
+            println!(
+                "slot,blockhash,bank_hash,start,end,non_vote_packets,non_vote_batches,\
+                 tpu_vote_packets,tpu_vote_batches,gossip_vote_packets,gossip_vote_batches,\
+                 valid_packets,staked_packets,unique_signatures,fees_offered"
+            );
+        } else {
+            println!(
+                "{:>12} {:>44} {:>44} {:>27} {:>27} {:>9} {:>8} {:>9} {:>8} {:>9} {:>8} {:>9} \
+                 {:>9} {:>9} {:>14}",
+                "slot",
+                "blockhash",
+                "bank_hash",
+                "start",
+                "end",
+                "nv_pkts",
+                "nv_bats",
+                "tv_pkts",
+                "tv_bats",
+                "gv_pkts",
+                "gv_bats",
+                "valid",
+                "staked",
+                "uniq_sigs",
+                "fees_offered",
+            );
+        }
+    }
+
+    /// Events after the last marker do not belong to a complete slot, so
+    /// they are not reported as a row.
+    fn report_trailing_events(&self) {
+        if self.current_slot.start.is_some() {
+            eprintln!("Events after the last BlockAndBankHash are not included");
+        }
+    }
+}
+
+/// Fee of a non-vote transaction, or zero if it is not a valid transaction.
+fn offered_fee(transaction: VersionedTransaction) -> u64 {
+    let Ok(transaction) = SanitizedVersionedTransaction::try_from(transaction) else {
+        return 0;
+    };
+    let Ok(compute_budget_limits) = compute_budget_limits(&transaction) else {
+        return 0;
+    };
+    transaction_fee(
+        transaction.get_signatures().len(),
+        transaction.get_message().program_instructions_iter(),
+        &compute_budget_limits,
+    )
+    .total()
+}
+
+fn format_timestamp(timestamp: SystemTime) -> String {
+    DateTime::<Utc>::from(timestamp)
+        .format("%Y-%m-%dT%H:%M:%S%.6fZ")
+        .to_string()
+}
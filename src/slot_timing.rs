@@ -0,0 +1,162 @@
+use {
+    crate::stats::{format_duration_percentiles, print_duration_histogram},
+    banking_trace_tool::process::process_event_files,
+    solana_clock::{Slot, NUM_CONSECUTIVE_LEADER_SLOTS},
+    solana_core::banking_trace::{TimedTracedEvent, TracedEvent},
+    std::{
+        ops::ControlFlow,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    },
+};
+
+/// Number of histogram buckets, each a quarter of the target slot time wide.
+const NUM_HISTOGRAM_BUCKETS: usize = 12;
+
+/// Report slot durations, leader window durations, and gaps between leader
+/// windows, from the timestamps of `BlockAndBankHash` events.
+///
+/// The `BlockAndBankHash` of a slot is recorded when the next leader slot
+/// starts, so the time between the markers of consecutive slots is the
+/// duration of the later slot. A leader window shows up as a run of
+/// consecutive markers, starting with the parent of its first slot.
+pub fn slot_timing(
+    event_file_paths: &[PathBuf],
+    target_slot_time: Duration,
+) -> std::io::Result<()> {
+    let mut handler = SlotTimingHandler::default();
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    handler.finish_window();
+    handler.report(target_slot_time);
+    Ok(())
+}
+
+#[derive(Default)]
+struct SlotTimingHandler {
+    last_marker: Option<(Slot, SystemTime)>,
+    /// First marker of the current run of consecutive slots.
+    window_start: Option<(Slot, SystemTime)>,
+
+    slot_durations: Vec<(Slot, Duration)>,
+    leader_windows: Vec<LeaderWindow>,
+    gaps: Vec<Gap>,
+}
+
+struct LeaderWindow {
+    /// Parent of the first slot in the window.
+    parent_slot: Slot,
+    last_slot: Slot,
+    duration: Duration,
+}
+
+struct Gap {
+    /// Last slot before the gap.
+    slot: Slot,
+    /// First slot after the gap.
+    next_slot: Slot,
+    duration: Duration,
+}
+
+impl SlotTimingHandler {
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        if let TracedEvent::BlockAndBankHash(slot, _, _) = event {
+            self.handle_block_and_bank_hash(timestamp, slot);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn handle_block_and_bank_hash(&mut self, timestamp: SystemTime, slot: Slot) {
+        if let Some((last_slot, last_timestamp)) = self.last_marker {
+            let duration = timestamp.duration_since(last_timestamp).unwrap_or_default();
+            if last_slot.saturating_add(1) == slot {
+                self.slot_durations.push((slot, duration));
+            } else {
+                self.finish_window();
+                self.gaps.push(Gap {
+                    slot: last_slot,
+                    next_slot: slot,
+                    duration,
+                });
+                self.window_start = None;
+            }
+        }
+        self.window_start.get_or_insert((slot, timestamp));
+        self.last_marker = Some((slot, timestamp));
+    }
+
+    /// Record the current run of consecutive slots as a leader window.
+    fn finish_window(&mut self) {
+        let (Some((parent_slot, start)), Some((last_slot, end))) =
+            (self.window_start, self.last_marker)
+        else {
+            return;
+        };
+        if last_slot > parent_slot {
+            self.leader_windows.push(LeaderWindow {
+                parent_slot,
+                last_slot,
+                duration: end.duration_since(start).unwrap_or_default(),
+            });
+        }
+    }
+
+    pub fn report(&self, target_slot_time: Duration) {
+        println!("Slots over {}ms:", target_slot_time.as_millis());
+        for (slot, duration) in &self.slot_durations {
+            if *duration > target_slot_time {
+                println!("  {slot}: {}ms", duration.as_millis());
+            }
+        }
+
+        println!("Leader windows:");
+        for window in &self.leader_windows {
+            let num_slots = window.last_slot - window.parent_slot;
+            let target = target_slot_time * num_slots as u32;
+            println!(
+                "  {}-{}: {num_slots} slots in {}ms{}",
+                window.parent_slot + 1,
+                window.last_slot,
+                window.duration.as_millis(),
+                if window.duration > target {
+                    format!(
+                        " ({}ms over target)",
+                        (window.duration - target).as_millis()
+                    )
+                } else {
+                    String::new()
+                }
+            );
+        }
+
+        // Leader windows are NUM_CONSECUTIVE_LEADER_SLOTS long, so any other
+        // number of missing slots points at skipped slots.
+        println!("Gaps:");
+        for gap in &self.gaps {
+            let num_missing = gap.next_slot.saturating_sub(gap.slot + 1);
+            println!(
+                "  {}-{}: {num_missing} slots missing over {}ms{}",
+                gap.slot,
+                gap.next_slot,
+                gap.duration.as_millis(),
+                if num_missing % NUM_CONSECUTIVE_LEADER_SLOTS != 0 {
+                    " (not a whole number of leader windows)"
+                } else {
+                    ""
+                }
+            );
+        }
+
+        let mut durations: Vec<_> = self
+            .slot_durations
+            .iter()
+            .map(|(_, duration)| *duration)
+            .collect();
+        durations.sort();
+        println!("Slot durations:");
+        println!("  {}", format_duration_percentiles(&durations));
+        print_duration_histogram(&durations, target_slot_time / 4, NUM_HISTOGRAM_BUCKETS);
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+/// Width of the widest bar printed by `print_duration_histogram`.
+const HISTOGRAM_WIDTH: usize = 50;
+
+/// Value at the `p`th percentile of `sorted_values`, using the nearest-rank
+/// method. Returns `None` if there are no values.
+pub fn percentile<T: Copy>(sorted_values: &[T], p: f64) -> Option<T> {
+    let rank = (p / 100.0 * sorted_values.len() as f64).ceil() as usize;
+    let index = rank.clamp(1, sorted_values.len().max(1)) - 1;
+    sorted_values.get(index).copied()
+}
+
+/// Mean of `values`, or zero if there are none.
+pub fn mean_duration(values: &[Duration]) -> Duration {
+    values.iter().sum::<Duration>() / values.len().max(1) as u32
+}
+
+/// Format the count, mean and common percentiles of `sorted_values` in ms.
+pub fn format_duration_percentiles(sorted_values: &[Duration]) -> String {
+    let format = |value: Option<Duration>| {
+        format!("{:.1}ms", value.unwrap_or_default().as_secs_f64() * 1000.0)
+    };
+    format!(
+        "count={} mean={} min={} p50={} p90={} p99={} max={}",
+        sorted_values.len(),
+        format(Some(mean_duration(sorted_values))),
+        format(sorted_values.first().copied()),
+        format(percentile(sorted_values, 50.0)),
+        format(percentile(sorted_values, 90.0)),
+        format(percentile(sorted_values, 99.0)),
+        format(sorted_values.last().copied()),
+    )
+}
+
+/// Print a histogram of `values` with `num_buckets` buckets of width
+/// `bucket_width`, and a final bucket for everything larger.
+pub fn print_duration_histogram(values: &[Duration], bucket_width: Duration, num_buckets: usize) {
+    let mut counts = vec![0usize; num_buckets + 1];
+    for value in values {
+        let bucket = (value.as_nanos() / bucket_width.as_nanos().max(1)) as usize;
+        counts[bucket.min(num_buckets)] += 1;
+    }
+
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1);
+    for (bucket, count) in counts.iter().enumerate() {
+        let start = bucket_width * bucket as u32;
+        let label = if bucket < num_buckets {
+            format!(
+                "{:>6}-{}ms",
+                start.as_millis(),
+                (start + bucket_width).as_millis()
+            )
+        } else {
+            format!("{:>6}ms+", start.as_millis())
+        };
+        let bar = "#".repeat(count * HISTOGRAM_WIDTH / max_count);
+        println!("  {label:<16} {count:>8} {bar}");
+    }
+}
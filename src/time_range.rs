@@ -1,8 +1,8 @@
 use {
-    crate::process::process_event_files,
+    banking_trace_tool::process::process_event_files,
     chrono::{DateTime, Utc},
     solana_core::banking_trace::TimedTracedEvent,
-    std::path::PathBuf,
+    std::{ops::ControlFlow, path::PathBuf},
 };
 
 pub fn time_range(event_file_paths: &[PathBuf]) -> std::io::Result<()> {
@@ -19,7 +19,10 @@ struct TimeRangeHandler {
 }
 
 impl TimeRangeHandler {
-    fn handle_event(&mut self, TimedTracedEvent(timestamp, _event): TimedTracedEvent) {
+    fn handle_event(
+        &mut self,
+        TimedTracedEvent(timestamp, _event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
         let timestamp = DateTime::<Utc>::from(timestamp);
         match &mut self.min {
             Some(min) => {
@@ -38,6 +41,7 @@ impl TimeRangeHandler {
                 self.max = Some(timestamp);
             }
         }
+        ControlFlow::Continue(())
     }
 
     fn report(&self) {
@@ -0,0 +1,86 @@
+use {
+    solana_core::banking_trace::TimedTracedEvent,
+    std::{
+        fs::File,
+        io::{BufWriter, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Size at which agave's banking tracer rotates event files.
+pub const DEFAULT_MAX_EVENT_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Writes events to a trace directory, in the same layout as agave's banking
+/// tracer. Events are bincode encoded back to back into `events`, and once it
+/// reaches the maximum file size, it is rotated to `events.1`, `events.1` to
+/// `events.2`, and so on. The written directory can be read back with
+/// [`TraceReader`](crate::TraceReader), or replayed by agave.
+pub struct TraceWriter {
+    directory: PathBuf,
+    max_file_size: u64,
+    writer: BufWriter<File>,
+    file_size: u64,
+    /// Number of rotated files, `events.1` through `events.{num_rotated}`.
+    num_rotated: usize,
+}
+
+impl TraceWriter {
+    /// Create `directory` if needed and start writing `events` in it. Fails if
+    /// the directory already contains a trace.
+    pub fn create(directory: impl AsRef<Path>, max_file_size: u64) -> std::io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory)?;
+        let event_file_path = directory.join("events");
+        if event_file_path.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists", event_file_path.display()),
+            ));
+        }
+        let writer = BufWriter::new(File::create(event_file_path)?);
+        Ok(Self {
+            directory,
+            max_file_size,
+            writer,
+            file_size: 0,
+            num_rotated: 0,
+        })
+    }
+
+    pub fn write_event(&mut self, event: &TimedTracedEvent) -> std::io::Result<()> {
+        let event_size = bincode::serialized_size(event).map_err(std::io::Error::other)?;
+        if self.file_size > 0 && self.file_size + event_size > self.max_file_size {
+            self.rotate()?;
+        }
+        bincode::serialize_into(&mut self.writer, event).map_err(std::io::Error::other)?;
+        self.file_size += event_size;
+        Ok(())
+    }
+
+    /// Flush the current event file. Dropping the writer without calling this
+    /// may lose buffered events.
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()
+    }
+
+    /// Shift every event file up by one index and start a new `events`.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        for index in (0..=self.num_rotated).rev() {
+            std::fs::rename(self.event_file_path(index), self.event_file_path(index + 1))?;
+        }
+        self.num_rotated += 1;
+        self.writer = BufWriter::new(File::create(self.event_file_path(0))?);
+        self.file_size = 0;
+        Ok(())
+    }
+
+    fn event_file_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.directory.join("events")
+        } else {
+            self.directory.join(format!("events.{index}"))
+        }
+    }
+}
@@ -1,26 +1,35 @@
 use {
-    crate::{cli::SlotRange, process::process_event_files},
+    crate::cli::SlotRange,
     agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::{process::process_event_files_from, seek_index::SeekTarget},
     solana_alt_store::{Store, UpdateMode},
     solana_clock::Slot,
     solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
     solana_transaction::versioned::VersionedTransaction,
-    std::{collections::HashSet, ops::RangeInclusive, path::PathBuf},
+    std::{
+        collections::HashSet,
+        ops::{ControlFlow, RangeInclusive},
+        path::PathBuf,
+    },
 };
 
 pub fn update_alt_store(
     event_file_paths: &[PathBuf],
     slot_range: SlotRange,
 ) -> std::io::Result<()> {
+    let start_slot = slot_range.start_slot;
     let mut handler = UpdateAddressLookupTableStoreHandler::new(slot_range);
-    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    process_event_files_from(
+        event_file_paths,
+        SeekTarget::Slot(start_slot),
+        &mut |event| handler.handle_event(event),
+    )?;
     Ok(())
 }
 
 struct UpdateAddressLookupTableStoreHandler {
     range: RangeInclusive<Slot>,
     current_packet_batches: Vec<BankingPacketBatch>,
-    done: bool,
     alt_store: Store,
 }
 
@@ -31,19 +40,18 @@ impl UpdateAddressLookupTableStoreHandler {
         Self {
             range: slot_range.start_slot..=slot_range.end_slot,
             current_packet_batches: Vec::new(),
-            done: false,
             alt_store: Store::load_or_create(ALT_STORE_PATH).expect("failed to load alt store"),
         }
     }
 
-    pub fn handle_event(&mut self, TimedTracedEvent(_timestamp, event): TimedTracedEvent) {
-        if self.done {
-            return;
-        }
-
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
         match event {
             TracedEvent::PacketBatch(label, packet_batches) => {
-                self.handle_packet_batches(label, packet_batches)
+                self.handle_packet_batches(label, packet_batches);
+                ControlFlow::Continue(())
             }
             TracedEvent::BlockAndBankHash(slot, _, _) => self.handle_block_and_bank_hash(slot),
         }
@@ -55,12 +63,12 @@ impl UpdateAddressLookupTableStoreHandler {
         }
     }
 
-    fn handle_block_and_bank_hash(&mut self, slot: Slot) {
+    fn handle_block_and_bank_hash(&mut self, slot: Slot) -> ControlFlow<()> {
         if !self.range.contains(&slot) {
             if slot > *self.range.end() {
-                self.done = true;
+                return ControlFlow::Break(());
             }
-            return;
+            return ControlFlow::Continue(());
         }
 
         // Collect unique ALT addresses
@@ -86,5 +94,6 @@ impl UpdateAddressLookupTableStoreHandler {
             .unwrap();
 
         self.current_packet_batches.clear();
+        ControlFlow::Continue(())
     }
 }
@@ -0,0 +1,67 @@
+use {
+    banking_trace_tool::process::{map_event_files, verify_event_file, EventFileReport},
+    chrono::{DateTime, Utc},
+    std::path::PathBuf,
+};
+
+pub fn verify(event_file_paths: &[PathBuf], keep_going: bool) -> std::io::Result<()> {
+    // Each file is verified independently, so check several in parallel.
+    let reports = map_event_files(event_file_paths, |path| verify_event_file(path, keep_going))
+        .into_iter()
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let mut num_bad_files = 0;
+    for report in &reports {
+        print_report(report);
+        num_bad_files += usize::from(!report.bad_regions.is_empty());
+    }
+
+    if num_bad_files > 0 {
+        return Err(std::io::Error::other(format!(
+            "{num_bad_files} of {} event files contain undecodable records",
+            reports.len()
+        )));
+    }
+    println!("All {} event files decoded cleanly", reports.len());
+    Ok(())
+}
+
+fn print_report(
+    EventFileReport {
+        path,
+        file_size,
+        num_events,
+        first_timestamp,
+        last_timestamp,
+        bad_regions,
+    }: &EventFileReport,
+) {
+    let format_timestamp = |timestamp: &Option<_>| {
+        timestamp
+            .map(|timestamp| DateTime::<Utc>::from(timestamp).to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    println!(
+        "{}: {num_events} events, {file_size} bytes, {} - {}",
+        path.display(),
+        format_timestamp(first_timestamp),
+        format_timestamp(last_timestamp),
+    );
+
+    for bad_region in bad_regions {
+        println!(
+            "  undecodable record at offset {} ({} trailing bytes): {}",
+            bad_region.offset, bad_region.trailing_bytes, bad_region.error
+        );
+        match bad_region.resync_offset {
+            Some(resync_offset) => println!(
+                "  resynced at offset {resync_offset} ({} bytes skipped)",
+                resync_offset - bad_region.offset
+            ),
+            None => println!(
+                "  no decodable record found after offset {}",
+                bad_region.offset
+            ),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use {
+    crate::stats::percentile,
+    agave_banking_stage_ingress_types::BankingPacketBatch,
+    banking_trace_tool::process::process_event_files,
+    solana_address::Address,
+    solana_clock::Slot,
+    solana_core::banking_trace::{ChannelLabel, TimedTracedEvent, TracedEvent},
+    solana_sdk_ids::vote,
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    solana_vote_interface::instruction::VoteInstruction,
+    std::{
+        collections::{HashMap, HashSet},
+        ops::ControlFlow,
+        path::PathBuf,
+    },
+};
+
+/// Report per vote account counts, voted slots and vote lateness for the
+/// `TpuVote` and `GossipVote` channels, and how much the channels overlap.
+///
+/// Lateness is measured against the latest `BlockAndBankHash` slot. Those are
+/// only recorded while this node is leader, so lateness is most meaningful for
+/// votes received during its leader windows.
+pub fn vote_stats(event_file_paths: &[PathBuf], account_count: usize) -> std::io::Result<()> {
+    let mut handler = VoteStatsHandler::default();
+    process_event_files(event_file_paths, &mut |event| handler.handle_event(event))?;
+    handler.report(account_count);
+    Ok(())
+}
+
+#[derive(Default)]
+struct VoteStatsHandler {
+    latest_slot: Option<Slot>,
+    tpu: ChannelVoteStats,
+    gossip: ChannelVoteStats,
+    vote_accounts: HashMap<Address, VoteAccountStats>,
+    /// Channels each vote signature was seen on.
+    signature_channels: HashMap<Signature, SeenOn>,
+}
+
+#[derive(Default)]
+struct ChannelVoteStats {
+    packets: usize,
+    votes: usize,
+    undecodable: usize,
+    /// Latest `BlockAndBankHash` slot minus voted slot, for each vote.
+    lateness: Vec<i64>,
+}
+
+#[derive(Default)]
+struct VoteAccountStats {
+    tpu_votes: usize,
+    gossip_votes: usize,
+    voted_slots: HashSet<Slot>,
+    lateness_sum: i64,
+    num_lateness: usize,
+}
+
+#[derive(Clone, Copy, Default)]
+struct SeenOn {
+    tpu: bool,
+    gossip: bool,
+}
+
+impl VoteStatsHandler {
+    pub fn handle_event(
+        &mut self,
+        TimedTracedEvent(_timestamp, event): TimedTracedEvent,
+    ) -> ControlFlow<()> {
+        match event {
+            TracedEvent::PacketBatch(label, packet_batches) => {
+                self.handle_packet_batches(label, packet_batches)
+            }
+            TracedEvent::BlockAndBankHash(slot, _, _) => {
+                self.latest_slot = Some(self.latest_slot.map_or(slot, |latest| latest.max(slot)));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn handle_packet_batches(&mut self, label: ChannelLabel, packet_batches: BankingPacketBatch) {
+        let is_tpu = match label {
+            ChannelLabel::TpuVote => true,
+            ChannelLabel::GossipVote => false,
+            ChannelLabel::NonVote | ChannelLabel::Dummy => return,
+        };
+
+        for packet in packet_batches.iter().flat_map(|b| b.iter()) {
+            let channel = if is_tpu {
+                &mut self.tpu
+            } else {
+                &mut self.gossip
+            };
+            channel.packets += 1;
+            let Some((signature, vote_account, voted_slot)) = packet
+                .data(..)
+                .and_then(|data| bincode::deserialize::<VersionedTransaction>(data).ok())
+                .and_then(|transaction| decode_vote(&transaction))
+            else {
+                channel.undecodable += 1;
+                continue;
+            };
+            channel.votes += 1;
+
+            let lateness = self
+                .latest_slot
+                .zip(voted_slot)
+                .map(|(latest_slot, voted_slot)| latest_slot as i64 - voted_slot as i64);
+            channel.lateness.extend(lateness);
+
+            let account = self.vote_accounts.entry(vote_account).or_default();
+            if is_tpu {
+                account.tpu_votes += 1;
+            } else {
+                account.gossip_votes += 1;
+            }
+            account.voted_slots.extend(voted_slot);
+            if let Some(lateness) = lateness {
+                account.lateness_sum += lateness;
+                account.num_lateness += 1;
+            }
+
+            let seen_on = self.signature_channels.entry(signature).or_default();
+            if is_tpu {
+                seen_on.tpu = true;
+            } else {
+                seen_on.gossip = true;
+            }
+        }
+    }
+
+    pub fn report(&self, account_count: usize) {
+        for (name, channel) in [("TPU", &self.tpu), ("Gossip", &self.gossip)] {
+            println!(
+                "{name} vote packets: {} votes={} undecodable={}",
+                channel.packets, channel.votes, channel.undecodable
+            );
+            let mut lateness = channel.lateness.clone();
+            lateness.sort();
+            let format = |value: Option<i64>| value.map_or("-".to_string(), |v| v.to_string());
+            println!(
+                "  lateness (slots): p50={} p90={} p99={} max={} ahead_of_latest={}",
+                format(percentile(&lateness, 50.0)),
+                format(percentile(&lateness, 90.0)),
+                format(percentile(&lateness, 99.0)),
+                format(lateness.last().copied()),
+                lateness.iter().filter(|lateness| **lateness < 0).count(),
+            );
+        }
+
+        let (mut tpu_only, mut gossip_only, mut both) = (0, 0, 0);
+        for seen_on in self.signature_channels.values() {
+            match (seen_on.tpu, seen_on.gossip) {
+                (true, true) => both += 1,
+                (true, false) => tpu_only += 1,
+                (false, true) => gossip_only += 1,
+                (false, false) => {}
+            }
+        }
+        println!(
+            "Unique vote signatures: {} tpu_only={tpu_only} gossip_only={gossip_only} both={both}",
+            self.signature_channels.len()
+        );
+
+        let mut vote_accounts: Vec<_> = self.vote_accounts.iter().collect();
+        vote_accounts
+            .sort_by_key(|(_, stats)| std::cmp::Reverse(stats.tpu_votes + stats.gossip_votes));
+        println!("Vote accounts: {}", vote_accounts.len());
+        println!("Top {account_count} vote accounts by votes:");
+        for (vote_account, stats) in vote_accounts.into_iter().take(account_count) {
+            let mean_lateness = (stats.num_lateness > 0)
+                .then(|| stats.lateness_sum as f64 / stats.num_lateness as f64);
+            println!(
+                "  {vote_account}: tpu={} gossip={} voted_slots={} range={}-{} mean_lateness={}",
+                stats.tpu_votes,
+                stats.gossip_votes,
+                stats.voted_slots.len(),
+                stats.voted_slots.iter().min().copied().unwrap_or_default(),
+                stats.voted_slots.iter().max().copied().unwrap_or_default(),
+                mean_lateness.map_or("-".to_string(), |lateness| format!("{lateness:.1}")),
+            );
+        }
+    }
+}
+
+/// Decode the first vote instruction of a vote transaction. Returns the
+/// transaction signature, the vote account, and the last voted slot.
+fn decode_vote(transaction: &VersionedTransaction) -> Option<(Signature, Address, Option<Slot>)> {
+    let signature = *transaction.signatures.first()?;
+    let account_keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .find_map(|instruction| {
+            let program_id = account_keys.get(usize::from(instruction.program_id_index))?;
+            if *program_id != vote::id() {
+                return None;
+            }
+            let vote_instruction =
+                bincode::deserialize::<VoteInstruction>(&instruction.data).ok()?;
+            if !vote_instruction.is_simple_vote() {
+                return None;
+            }
+            let vote_account = account_keys.get(usize::from(*instruction.accounts.first()?))?;
+            Some((signature, *vote_account, vote_instruction.last_voted_slot()))
+        })
+}